@@ -0,0 +1,52 @@
+use std::hint::black_box;
+use std::sync::{LazyLock, Once, OnceLock};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use wipe_on_fork::{WipeOnForkLazyLock, WipeOnForkOnce, WipeOnForkOnceLock};
+
+fn once(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Once::is_completed");
+
+    let std_once = Once::new();
+    std_once.call_once(|| {});
+    group.bench_function("std", |b| b.iter(|| black_box(&std_once).is_completed()));
+
+    let once = WipeOnForkOnce::new();
+    once.call_once(|| {});
+    group.bench_function("wipe_on_fork", |b| {
+        b.iter(|| black_box(&once).is_completed())
+    });
+
+    group.finish();
+}
+
+fn once_lock(c: &mut Criterion) {
+    let mut group = c.benchmark_group("OnceLock::get");
+
+    let std_lock = OnceLock::new();
+    std_lock.set(92u64).unwrap();
+    group.bench_function("std", |b| b.iter(|| *black_box(&std_lock).get().unwrap()));
+
+    let lock = WipeOnForkOnceLock::new();
+    lock.set(92u64).unwrap();
+    group.bench_function("wipe_on_fork", |b| {
+        b.iter(|| *black_box(&lock).get().unwrap())
+    });
+
+    group.finish();
+}
+
+fn lazy_lock(c: &mut Criterion) {
+    let mut group = c.benchmark_group("LazyLock::deref");
+
+    let std_lazy = LazyLock::new(|| 92u64);
+    group.bench_function("std", |b| b.iter(|| **black_box(&std_lazy)));
+
+    let lazy = WipeOnForkLazyLock::new(|| 92u64);
+    group.bench_function("wipe_on_fork", |b| b.iter(|| **black_box(&lazy)));
+
+    group.finish();
+}
+
+criterion_group!(benches, once, once_lock, lazy_lock);
+criterion_main!(benches);
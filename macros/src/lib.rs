@@ -0,0 +1,120 @@
+//! The `#[wipe_on_fork]` attribute of the [`wipe-on-fork`](https://docs.rs/wipe-on-fork) crate,
+//! available there as `wipe_on_fork::wipe_on_fork` with the `macros` feature, where its examples
+//! are.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Error, ItemStatic, LitStr, StaticMutability};
+
+#[derive(Clone, Copy)]
+enum Policy {
+    Wipe,
+    Reinit,
+    Keep,
+}
+
+#[derive(Clone, Copy)]
+enum OnWipe {
+    Drop,
+    Forget,
+}
+
+/// Turns a `static` into a `WipeOnForkLazyLock`, whose initializer is the expression of the
+/// `static` and runs again in each child process.
+///
+/// The value inherited from the parent can be handled differently with two options:
+///
+/// - `policy`: `"wipe"` (the default) computes the value again when it is next needed, `"reinit"`
+///   computes it again eagerly on the first access in the child, and `"keep"` keeps the parent's
+///   value.
+/// - `on_wipe`: `"drop"` (the default) drops the inherited value, and `"forget"` leaks it
+///   instead, for values whose `Drop` must not run in the child.
+#[proc_macro_attribute]
+pub fn wipe_on_fork(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut policy = Policy::Wipe;
+    let mut on_wipe = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("policy") {
+            let value: LitStr = meta.value()?.parse()?;
+            policy = match value.value().as_str() {
+                "wipe" => Policy::Wipe,
+                "reinit" => Policy::Reinit,
+                "keep" => Policy::Keep,
+                _ => {
+                    return Err(Error::new(
+                        value.span(),
+                        "expected \"wipe\", \"reinit\", or \"keep\"",
+                    ))
+                }
+            };
+            Ok(())
+        } else if meta.path.is_ident("on_wipe") {
+            let value: LitStr = meta.value()?.parse()?;
+            on_wipe = match value.value().as_str() {
+                "drop" => Some((OnWipe::Drop, value.span())),
+                "forget" => Some((OnWipe::Forget, value.span())),
+                _ => return Err(Error::new(value.span(), "expected \"drop\" or \"forget\"")),
+            };
+            Ok(())
+        } else {
+            Err(meta.error("unsupported option, expected `policy` or `on_wipe`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+    let item = parse_macro_input!(item as ItemStatic);
+
+    match expand(policy, on_wipe, item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(
+    policy: Policy,
+    on_wipe: Option<(OnWipe, Span)>,
+    item: ItemStatic,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if let StaticMutability::Mut(token) = item.mutability {
+        return Err(Error::new_spanned(
+            token,
+            "#[wipe_on_fork] does not support `static mut`",
+        ));
+    }
+
+    let ItemStatic {
+        attrs,
+        vis,
+        ident,
+        ty,
+        expr,
+        ..
+    } = item;
+
+    let policy = match (policy, on_wipe) {
+        (Policy::Keep, Some((_, span))) => {
+            return Err(Error::new(
+                span,
+                "`on_wipe` cannot be used with `policy = \"keep\"`",
+            ));
+        }
+        (Policy::Keep, None) => quote!(::wipe_on_fork::ForkPolicy::Keep),
+        (Policy::Wipe, None | Some((OnWipe::Drop, _))) => quote!(::wipe_on_fork::ForkPolicy::Wipe),
+        (Policy::Wipe, Some((OnWipe::Forget, _))) => quote!(::wipe_on_fork::ForkPolicy::Forget),
+        (Policy::Reinit, None | Some((OnWipe::Drop, _))) => {
+            quote!(::wipe_on_fork::ForkPolicy::Reinit(|| #expr))
+        }
+        (Policy::Reinit, Some((OnWipe::Forget, _))) => quote! {
+            ::wipe_on_fork::ForkPolicy::Custom(|slot| {
+                ::core::mem::forget(slot.take());
+                *slot = ::core::option::Option::Some(#expr);
+            })
+        },
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis static #ident: ::wipe_on_fork::WipeOnForkLazyLock<#ty> =
+            ::wipe_on_fork::WipeOnForkLazyLock::with_policy(|| #expr, #policy);
+    })
+}
@@ -0,0 +1,191 @@
+use crate::fork::ForkToken;
+use crate::{WipeOnForkMutex, WipeOnForkOnceLock};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::PoisonError;
+use std::task::{Context, Poll, Waker};
+
+#[derive(Default)]
+struct Gate {
+    running: bool,
+    wakers: Vec<Waker>,
+}
+
+/// A `OnceLock`-like cell that is initialized by a future, and wiped in the child process.
+///
+/// It does not depend on any async runtime. Only one initializer runs at a time: the other
+/// callers of [`get_or_init`](Self::get_or_init) wait for it without blocking their thread, and
+/// one of them runs its own initializer if the first one fails or is cancelled (i.e., its future
+/// is dropped before it completes).
+///
+/// The initializer runs again in a child process, even if the parent was in the middle of
+/// running one at the time of the fork.
+///
+/// ```
+/// use wipe_on_fork::WipeOnForkAsyncOnceCell;
+///
+/// static POOL: WipeOnForkAsyncOnceCell<Vec<String>> = WipeOnForkAsyncOnceCell::new();
+///
+/// async fn connect() -> Vec<String> {
+///     vec!["db-1".to_string(), "db-2".to_string()]
+/// }
+///
+/// async fn handle_request() -> usize {
+///     POOL.get_or_init(connect).await.len()
+/// }
+/// ```
+pub struct WipeOnForkAsyncOnceCell<T> {
+    value: WipeOnForkOnceLock<T>,
+    gate: WipeOnForkMutex<Gate>,
+}
+
+impl<T> WipeOnForkAsyncOnceCell<T> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> WipeOnForkAsyncOnceCell<T> {
+        WipeOnForkAsyncOnceCell {
+            value: WipeOnForkOnceLock::new(),
+            gate: WipeOnForkMutex::new(Gate::default),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.value.get_mut()
+    }
+
+    #[inline]
+    pub fn set(&self, value: T) -> Result<(), T> {
+        self.value.set(value)
+    }
+
+    /// Returns the value, initializing it with the future returned by `f` if it is not.
+    pub async fn get_or_init<F, Fut>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let init = || async { Ok::<T, core::convert::Infallible>(f().await) };
+        match self.get_or_try_init(init).await {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Returns the value, initializing it with the future returned by `f` if it is not. If the
+    /// future fails, the cell stays uninitialized, and the next caller runs its initializer.
+    ///
+    /// ```
+    /// use wipe_on_fork::WipeOnForkAsyncOnceCell;
+    ///
+    /// async fn example() {
+    ///     let cell: WipeOnForkAsyncOnceCell<u32> = WipeOnForkAsyncOnceCell::new();
+    ///     assert_eq!(cell.get_or_try_init(|| async { Err(()) }).await, Err(()));
+    ///     assert_eq!(cell.get_or_try_init(|| async { Ok::<u32, ()>(92) }).await, Ok(&92));
+    /// }
+    /// ```
+    pub async fn get_or_try_init<F, Fut, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+
+        let _permit = Acquire { cell: self }.await;
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+
+        let value = f().await?;
+        Ok(self.value.get_or_init(|| value))
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        self.value.into_inner()
+    }
+
+    #[inline]
+    pub fn take(&mut self) -> Option<T> {
+        self.value.take()
+    }
+}
+
+struct Acquire<'a, T> {
+    cell: &'a WipeOnForkAsyncOnceCell<T>,
+}
+
+impl<'a, T> Future for Acquire<'a, T> {
+    type Output = Permit<'a, T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Permit<'a, T>> {
+        let mut gate = self
+            .cell
+            .gate
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if !gate.running {
+            gate.running = true;
+            return Poll::Ready(Permit {
+                cell: self.cell,
+                token: ForkToken::new(),
+            });
+        }
+        if !gate.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            gate.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+// Held while an initializer runs, and released when it finishes or its future is dropped.
+struct Permit<'a, T> {
+    cell: &'a WipeOnForkAsyncOnceCell<T>,
+    token: ForkToken,
+}
+
+impl<T> Drop for Permit<'_, T> {
+    fn drop(&mut self) {
+        // A permit from the parent does not own the gate of the child.
+        if self.token.is_stale() {
+            return;
+        }
+        let wakers = {
+            let mut gate = self
+                .cell
+                .gate
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            gate.running = false;
+            core::mem::take(&mut gate.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Default for WipeOnForkAsyncOnceCell<T> {
+    #[inline]
+    fn default() -> WipeOnForkAsyncOnceCell<T> {
+        WipeOnForkAsyncOnceCell::new()
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for WipeOnForkAsyncOnceCell<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_tuple("WipeOnForkAsyncOnceCell");
+        match self.get() {
+            Some(v) => d.field(v),
+            None => d.field(&format_args!("<uninit>")),
+        };
+        d.finish()
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{ForkPolicy, WipeOnForkOnceLock};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
+
+/// What [`WipeOnForkFd`] does with a file descriptor inherited from the parent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum FdPolicy {
+    /// Close the child's copy of the file descriptor with `close()`, which does not affect the
+    /// parent's copy. The cell is empty in the child. This is the default.
+    #[default]
+    Close,
+    /// Keep the file descriptor, shared with the parent, in the cell.
+    Keep,
+    /// Leave the file descriptor open but empty the cell, so that the child does not use it, nor
+    /// close it.
+    Leak,
+}
+
+impl FdPolicy {
+    const fn to_fork_policy(self) -> ForkPolicy<OwnedFd> {
+        match self {
+            FdPolicy::Close => ForkPolicy::Wipe,
+            FdPolicy::Keep => ForkPolicy::Keep,
+            FdPolicy::Leak => ForkPolicy::Forget,
+        }
+    }
+}
+
+/// A `OnceLock<OwnedFd>` for a file descriptor that each process opens for itself, such as a
+/// connection to a server or an epoll instance.
+///
+/// After a fork, the parent and the child share the file descriptors that the parent opened.
+/// A child that uses an inherited connection interleaves its traffic with the parent's, and a
+/// wrapper that shuts it down on drop (e.g., a TLS stream) breaks the parent's connection. Here,
+/// the child finds the cell empty, and opens its own file descriptor, while the inherited one is
+/// handled as the [`FdPolicy`] says.
+///
+/// ```
+/// use std::os::fd::OwnedFd;
+///
+/// use wipe_on_fork::WipeOnForkFd;
+///
+/// static LOG: WipeOnForkFd = WipeOnForkFd::new();
+///
+/// let fd = LOG
+///     .get_or_try_init(|| std::fs::File::open("/dev/null").map(OwnedFd::from))
+///     .unwrap();
+/// wipe_on_fork::set_cloexec(fd).unwrap();
+/// ```
+pub struct WipeOnForkFd {
+    fd: WipeOnForkOnceLock<OwnedFd>,
+}
+
+impl WipeOnForkFd {
+    /// Creates an empty cell that closes the inherited file descriptor in the child.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> WipeOnForkFd {
+        WipeOnForkFd::with_policy(FdPolicy::Close)
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn with_policy(policy: FdPolicy) -> WipeOnForkFd {
+        WipeOnForkFd {
+            fd: WipeOnForkOnceLock::with_policy(policy.to_fork_policy()),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self) -> Option<BorrowedFd<'_>> {
+        self.fd.get().map(AsFd::as_fd)
+    }
+
+    #[inline]
+    pub fn set(&self, fd: OwnedFd) -> Result<(), OwnedFd> {
+        self.fd.set(fd)
+    }
+
+    #[inline]
+    pub fn get_or_init<F>(&self, f: F) -> BorrowedFd<'_>
+    where
+        F: FnOnce() -> OwnedFd,
+    {
+        self.fd.get_or_init(f).as_fd()
+    }
+
+    #[inline]
+    pub fn get_or_try_init<F, E>(&self, f: F) -> Result<BorrowedFd<'_>, E>
+    where
+        F: FnOnce() -> Result<OwnedFd, E>,
+    {
+        self.fd.get_or_try_init(f).map(AsFd::as_fd)
+    }
+
+    #[inline]
+    pub fn take(&mut self) -> Option<OwnedFd> {
+        self.fd.take()
+    }
+
+    #[inline]
+    pub fn into_inner(mut self) -> Option<OwnedFd> {
+        self.fd.take()
+    }
+}
+
+impl Drop for WipeOnForkFd {
+    fn drop(&mut self) {
+        // An inherited file descriptor is handled as the policy says, e.g., left open with
+        // `FdPolicy::Leak`, even if the cell is dropped before its first use in the child.
+        self.fd.discard_inherited();
+    }
+}
+
+impl Default for WipeOnForkFd {
+    #[inline]
+    fn default() -> WipeOnForkFd {
+        WipeOnForkFd::new()
+    }
+}
+
+impl core::fmt::Debug for WipeOnForkFd {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_tuple("WipeOnForkFd");
+        match self.get() {
+            Some(fd) => d.field(&fd.as_raw_fd()),
+            None => d.field(&format_args!("<uninit>")),
+        };
+        d.finish()
+    }
+}
+
+/// Sets `FD_CLOEXEC` on the file descriptor, so that it is closed when the process calls
+/// `exec()`, as is usual after `fork()`.
+pub fn set_cloexec<Fd: AsFd>(fd: Fd) -> std::io::Result<()> {
+    let fd = fd.as_fd().as_raw_fd();
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFD);
+        if flags == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        if flags & libc::FD_CLOEXEC == 0
+            && libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) == -1
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
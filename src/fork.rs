@@ -0,0 +1,180 @@
+//! Fork generations and fork event callbacks.
+//!
+//! Every process starts in generation `0`, and a forked child is one generation after its
+//! parent, unless a [`ForkDetector`](crate::ForkDetector) that counts differently is installed.
+//! This is what the `WipeOnFork*` types compare against to decide whether their value was
+//! inherited. The functions here expose the same information to other code.
+//!
+//! ```
+//! use wipe_on_fork::fork::{self, ForkToken};
+//!
+//! let token = ForkToken::new();
+//! assert_eq!(token.generation(), fork::generation());
+//! assert!(!token.is_stale());
+//! ```
+
+use crate::utils::GENERATION;
+use alloc::boxed::Box;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// Returns the fork generation of the current process.
+#[inline]
+pub fn generation() -> u64 {
+    crate::utils::generation()
+}
+
+/// Remembers the fork generation in which it was created.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ForkToken {
+    generation: u64,
+}
+
+impl ForkToken {
+    #[inline]
+    pub fn new() -> ForkToken {
+        ForkToken {
+            generation: generation(),
+        }
+    }
+
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns `true` if the current process was forked (directly or not) since the token was
+    /// created.
+    #[inline]
+    pub fn is_stale(&self) -> bool {
+        self.generation != generation()
+    }
+}
+
+impl Default for ForkToken {
+    #[inline]
+    fn default() -> ForkToken {
+        ForkToken::new()
+    }
+}
+
+struct Node {
+    f: Box<dyn Fn() + Send + Sync>,
+    next: *mut Node,
+}
+
+// An append-only list that can be traversed without locking or allocating, which is required
+// in the child right after `fork()`. Nodes are never freed.
+struct Callbacks {
+    head: AtomicPtr<Node>,
+}
+
+impl Callbacks {
+    const fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(core::ptr::null_mut()),
+        }
+    }
+
+    fn push(&self, f: Box<dyn Fn() + Send + Sync>) {
+        let node = Box::into_raw(Box::new(Node {
+            f,
+            next: core::ptr::null_mut(),
+        }));
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            unsafe { (*node).next = head };
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    fn run_newest_first(&self) {
+        let mut node = self.head.load(Ordering::Acquire);
+        while !node.is_null() {
+            unsafe {
+                ((*node).f)();
+                node = (*node).next;
+            }
+        }
+    }
+
+    fn run_oldest_first(&self) {
+        fn run(node: *mut Node) {
+            if !node.is_null() {
+                unsafe {
+                    run((*node).next);
+                    ((*node).f)();
+                }
+            }
+        }
+        run(self.head.load(Ordering::Acquire));
+    }
+}
+
+static PREPARE: Callbacks = Callbacks::new();
+static PARENT: Callbacks = Callbacks::new();
+static CHILD: Callbacks = Callbacks::new();
+
+/// Registers a callback that runs in the parent right before `fork()`.
+///
+/// Like `pthread_atfork`, prepare callbacks run in the reverse order of their registration.
+pub fn on_prepare<F>(f: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    GENERATION.get();
+    PREPARE.push(Box::new(f));
+}
+
+/// Registers a callback that runs in the parent right after `fork()`.
+///
+/// Parent callbacks run in the order of their registration.
+pub fn on_parent<F>(f: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    GENERATION.get();
+    PARENT.push(Box::new(f));
+}
+
+/// Registers a callback that runs in the child right after `fork()`.
+///
+/// Child callbacks run in the order of their registration, after the crate has moved the child
+/// to the next generation. So, [`generation`] already returns the child's generation, and tokens
+/// created in the parent are stale. The `WipeOnFork*` values themselves are only wiped when
+/// they are next accessed.
+///
+/// If the parent has more than one thread, the callback must only do async-signal-safe work:
+/// it must not allocate, take locks, or panic.
+///
+/// ```
+/// use wipe_on_fork::fork;
+///
+/// fork::on_child(|| {
+///     // for example, reset a counter that is kept in an atomic
+/// });
+/// ```
+pub fn on_child<F>(f: F)
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    GENERATION.get();
+    CHILD.push(Box::new(f));
+}
+
+pub(crate) fn run_prepare_callbacks() {
+    PREPARE.run_newest_first();
+}
+
+pub(crate) fn run_parent_callbacks() {
+    PARENT.run_oldest_first();
+}
+
+pub(crate) fn run_child_callbacks() {
+    CHILD.run_oldest_first();
+}
@@ -0,0 +1,60 @@
+use core::sync::atomic::AtomicU32;
+
+/// Blocks the current thread while `futex` holds `expected`. It may also return spuriously, so
+/// the caller has to check its condition again.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[inline]
+pub(crate) fn wait(futex: &AtomicU32, expected: u32) {
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            futex as *const AtomicU32,
+            libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+            expected,
+            core::ptr::null::<libc::timespec>(),
+        );
+    }
+}
+
+/// Wakes up all the threads blocked in [`wait`] on `futex`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[inline]
+pub(crate) fn wake_all(futex: &AtomicU32) {
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            futex as *const AtomicU32,
+            libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+            i32::MAX,
+        );
+    }
+}
+
+// Without futexes, waiters give up their time slice and check again. Initializers that block for
+// long are rare enough that this is not worth a parking lot.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+#[inline]
+pub(crate) fn wait(futex: &AtomicU32, expected: u32) {
+    if futex.load(core::sync::atomic::Ordering::Acquire) == expected {
+        yield_now();
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+#[inline]
+pub(crate) fn wake_all(_futex: &AtomicU32) {}
+
+/// Gives up the time slice of the current thread.
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn yield_now() {
+    std::thread::yield_now();
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn yield_now() {
+    unsafe {
+        libc::sched_yield();
+    }
+}
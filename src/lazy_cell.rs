@@ -1,6 +1,7 @@
+use crate::ForkPolicy;
 use core::cell::{Cell, UnsafeCell};
 use core::marker::PhantomData;
-use std::ops::Deref;
+use core::ops::Deref;
 
 enum State<T, F> {
     Uninit(F),
@@ -28,6 +29,7 @@ enum State<T, F> {
 pub struct WipeOnForkLazyCell<T, F = fn() -> T> {
     generation_id: Cell<Option<u64>>,
     state: UnsafeCell<State<T, F>>,
+    policy: ForkPolicy<T>,
     _not_send_sync: PhantomData<*const ()>,
 }
 
@@ -43,9 +45,24 @@ impl<T, F: FnMut() -> T> WipeOnForkLazyCell<T, F> {
     /// ```
     #[inline]
     pub const fn new(f: F) -> Self {
+        WipeOnForkLazyCell::with_policy(f, ForkPolicy::Wipe)
+    }
+
+    /// Creates a new lazy value with the given policy for the value inherited from the parent.
+    ///
+    /// ```
+    /// use wipe_on_fork::{ForkPolicy, WipeOnForkLazyCell};
+    ///
+    /// let lazy = WipeOnForkLazyCell::with_policy(|| vec![0u8; 16], ForkPolicy::Forget);
+    ///
+    /// assert_eq!(lazy.len(), 16);
+    /// ```
+    #[inline]
+    pub const fn with_policy(f: F, policy: ForkPolicy<T>) -> Self {
         WipeOnForkLazyCell {
             generation_id: Cell::new(None),
             state: UnsafeCell::new(State::Uninit(f)),
+            policy,
             _not_send_sync: PhantomData,
         }
     }
@@ -99,7 +116,7 @@ impl<T, F: FnMut() -> T> WipeOnForkLazyCell<T, F> {
 
         unsafe { this.state.get().write(State::Init(data, f)) };
 
-        this.generation_id.set(Some(crate::utils::GENERATION.get()));
+        this.generation_id.set(Some(crate::utils::generation()));
 
         let state = unsafe { &*this.state.get() };
         let State::Init(data, _) = state else {
@@ -113,10 +130,10 @@ impl<T, F> WipeOnForkLazyCell<T, F> {
     #[cfg(unix)]
     #[inline]
     fn check_if_should_wipe(&self) -> bool {
-        return match self.generation_id.get() {
+        match self.generation_id.get() {
             None => false,
-            Some(generation_id) => generation_id != crate::utils::GENERATION.get(),
-        };
+            Some(generation_id) => generation_id != crate::utils::generation(),
+        }
     }
 
     #[cfg(not(unix))]
@@ -130,20 +147,23 @@ impl<T, F> WipeOnForkLazyCell<T, F> {
         if self.check_if_should_wipe() {
             self.generation_id.set(None);
 
-            let is_state_init = unsafe {
-                match *self.state.get() {
-                    State::Init(_, _) => true,
-                    _ => false,
-                }
-            };
+            let is_state_init = unsafe { matches!(*self.state.get(), State::Init(_, _)) };
 
             if is_state_init {
                 let state = unsafe { &mut *self.state.get() };
-                let State::Init(_, f) = core::mem::replace(state, State::Poisoned) else {
+                let State::Init(data, f) = core::mem::replace(state, State::Poisoned) else {
                     unreachable!()
                 };
 
-                unsafe { self.state.get().write(State::Uninit(f)) };
+                let mut slot = Some(data);
+                let state = if self.policy.apply(&mut slot) {
+                    self.generation_id.set(Some(crate::utils::generation()));
+                    State::Init(slot.unwrap(), f)
+                } else {
+                    State::Uninit(f)
+                };
+
+                unsafe { self.state.get().write(state) };
             }
         }
     }
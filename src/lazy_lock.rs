@@ -1,9 +1,9 @@
 use crate::once::ExclusiveState;
-use crate::WipeOnForkOnce;
-use std::cell::UnsafeCell;
-use std::mem::ManuallyDrop;
-use std::ops::Deref;
-use std::panic::{RefUnwindSafe, UnwindSafe};
+use crate::{ForkPolicy, PoisonPolicy, WipeOnForkOnce};
+use core::cell::UnsafeCell;
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
+use core::panic::{RefUnwindSafe, UnwindSafe};
 
 /// ```
 /// use std::collections::HashMap;
@@ -47,20 +47,70 @@ use std::panic::{RefUnwindSafe, UnwindSafe};
 ///     println!("{}", *data.number);
 /// }
 /// ```
-
 pub struct WipeOnForkLazyLock<T, F = fn() -> T> {
     once: WipeOnForkOnce,
-    func: UnsafeCell<ManuallyDrop<F>>,
-    data: UnsafeCell<ManuallyDrop<Option<T>>>,
+    func: UnsafeCell<F>,
+    data: UnsafeCell<Option<T>>,
+    policy: ForkPolicy<T>,
 }
 
 impl<T, F: FnMut() -> T> WipeOnForkLazyLock<T, F> {
     #[inline]
     pub const fn new(f: F) -> WipeOnForkLazyLock<T, F> {
+        WipeOnForkLazyLock::with_policy(f, ForkPolicy::Wipe)
+    }
+
+    /// Creates a new lazy value with the given policy for the value inherited from the parent.
+    ///
+    /// ```
+    /// use wipe_on_fork::{ForkPolicy, WipeOnForkLazyLock};
+    ///
+    /// static PID: WipeOnForkLazyLock<u32> =
+    ///     WipeOnForkLazyLock::with_policy(std::process::id, ForkPolicy::Reinit(std::process::id));
+    ///
+    /// assert_eq!(*PID, std::process::id());
+    /// ```
+    #[inline]
+    pub const fn with_policy(f: F, policy: ForkPolicy<T>) -> WipeOnForkLazyLock<T, F> {
+        WipeOnForkLazyLock::with_policies(f, policy, PoisonPolicy::Poison)
+    }
+
+    /// Creates a new lazy value with the given policies for the value inherited from the parent,
+    /// and for initializers that panic. With [`PoisonPolicy::Retry`], the next access after a
+    /// panic runs `f` again instead of panicking.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// use wipe_on_fork::{ForkPolicy, PoisonPolicy, WipeOnForkLazyLock};
+    ///
+    /// static READY: AtomicBool = AtomicBool::new(false);
+    /// static CONFIG: WipeOnForkLazyLock<u32> = WipeOnForkLazyLock::with_policies(
+    ///     || {
+    ///         assert!(READY.load(Ordering::Acquire), "not ready yet");
+    ///         92
+    ///     },
+    ///     ForkPolicy::Wipe,
+    ///     PoisonPolicy::Retry,
+    /// );
+    ///
+    /// assert!(std::thread::spawn(|| *CONFIG).join().is_err());
+    /// assert!(!WipeOnForkLazyLock::is_poisoned(&CONFIG));
+    ///
+    /// READY.store(true, Ordering::Release);
+    /// assert_eq!(*CONFIG, 92);
+    /// ```
+    #[inline]
+    pub const fn with_policies(
+        f: F,
+        policy: ForkPolicy<T>,
+        poison_policy: PoisonPolicy,
+    ) -> WipeOnForkLazyLock<T, F> {
         WipeOnForkLazyLock {
-            once: WipeOnForkOnce::new(),
-            func: UnsafeCell::new(ManuallyDrop::new(f)),
-            data: UnsafeCell::new(ManuallyDrop::new(None)),
+            once: WipeOnForkOnce::with_poison_policy(poison_policy),
+            func: UnsafeCell::new(f),
+            data: UnsafeCell::new(None),
+            policy,
         }
     }
 
@@ -75,22 +125,20 @@ impl<T, F: FnMut() -> T> WipeOnForkLazyLock<T, F> {
     /// assert_eq!(WipeOnForkLazyLock::into_inner(lazy).ok(), Some("HELLO, WORLD!".to_string()));
     /// ```
     pub fn into_inner(mut this: Self) -> Result<T, F> {
+        this.apply_policy_if_should_wipe();
         let state = this.once.state();
+        if let ExclusiveState::Poisoned = state {
+            panic!("LazyLock instance has previously been poisoned");
+        }
+
+        // `Drop` is implemented, so the fields have to be moved out manually.
+        let this = ManuallyDrop::new(this);
+        let func = unsafe { core::ptr::read(&this.func) }.into_inner();
+        let data = unsafe { core::ptr::read(&this.data) }.into_inner();
         match state {
-            ExclusiveState::Poisoned => panic!("LazyLock instance has previously been poisoned"),
-            state => unsafe {
-                let this = ManuallyDrop::new(this);
-                match state {
-                    ExclusiveState::Incomplete => Err(ManuallyDrop::into_inner(
-                        std::ptr::read(&this.func).into_inner(),
-                    )),
-                    ExclusiveState::Complete => Ok(ManuallyDrop::into_inner(
-                        std::ptr::read(&this.data).into_inner(),
-                    )
-                    .unwrap()),
-                    ExclusiveState::Poisoned => unreachable!(),
-                }
-            },
+            ExclusiveState::Incomplete => Err(func),
+            ExclusiveState::Complete => Ok(data.unwrap()),
+            ExclusiveState::Poisoned => unreachable!(),
         }
     }
 
@@ -104,20 +152,48 @@ impl<T, F: FnMut() -> T> WipeOnForkLazyLock<T, F> {
     /// ```
     #[inline]
     pub fn force(this: &WipeOnForkLazyLock<T, F>) -> &T {
+        this.apply_policy_if_should_wipe();
+        // The initializer is kept, rather than moved out, because it runs again in every child
+        // process that needs the value. The `Once` gives it exclusive access.
         this.once.call_once(|| unsafe {
-            let mut f = ManuallyDrop::take(&mut *this.func.get());
-            let value = f();
-            *this.data.get() = ManuallyDrop::new(Some(value));
+            let value = (*this.func.get())();
+            // Like in `WipeOnForkOnceLock`, this leaks what an initializer of the parent may have
+            // left when it was interrupted by a fork.
+            this.data.get().write(Some(value));
         });
 
-        unsafe { &*(*this.data.get()).as_ref().unwrap() }
+        unsafe { (*this.data.get()).as_ref().unwrap() }
     }
 }
 
 impl<T, F> WipeOnForkLazyLock<T, F> {
+    #[inline]
+    fn apply_policy_if_should_wipe(&self) {
+        self.once
+            .wipe_if_should_wipe_with(|| self.policy.apply(unsafe { &mut *self.data.get() }));
+    }
+
+    /// Returns `true` if the initializer panicked, in which case accessing the value panics too.
+    /// This never happens with [`PoisonPolicy::Retry`].
+    ///
+    /// ```
+    /// use wipe_on_fork::WipeOnForkLazyLock;
+    ///
+    /// static BROKEN: WipeOnForkLazyLock<u32> = WipeOnForkLazyLock::new(|| panic!());
+    ///
+    /// assert!(std::thread::spawn(|| *BROKEN).join().is_err());
+    /// assert!(WipeOnForkLazyLock::is_poisoned(&BROKEN));
+    /// ```
+    #[inline]
+    pub fn is_poisoned(this: &WipeOnForkLazyLock<T, F>) -> bool {
+        this.apply_policy_if_should_wipe();
+        this.once.is_poisoned()
+    }
+
     fn get(&self) -> Option<&T> {
+        self.apply_policy_if_should_wipe();
         if self.once.is_completed() {
-            Some(unsafe { &*(*self.data.get()).as_ref().unwrap() })
+            Some(unsafe { (*self.data.get()).as_ref().unwrap() })
         } else {
             None
         }
@@ -126,11 +202,13 @@ impl<T, F> WipeOnForkLazyLock<T, F> {
 
 impl<T, F> Drop for WipeOnForkLazyLock<T, F> {
     fn drop(&mut self) {
-        match self.once.state() {
-            ExclusiveState::Incomplete => unsafe { ManuallyDrop::drop(&mut self.func.get_mut()) },
-            ExclusiveState::Complete => unsafe { ManuallyDrop::drop(&mut self.data.get_mut()) },
-            ExclusiveState::Poisoned => {}
-        }
+        // A value inherited from the parent is discarded as the policy says (e.g., leaked with
+        // `ForkPolicy::Forget`) rather than dropped with the other fields, but never recomputed.
+        self.once.wipe_if_should_wipe_with(|| {
+            let slot = unsafe { &mut *self.data.get() };
+            self.policy.discard(slot);
+            slot.is_some()
+        });
     }
 }
 
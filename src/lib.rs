@@ -1,3 +1,10 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(not(unix), not(feature = "std")))]
+compile_error!("wipe-on-fork needs the `std` feature outside of Unix");
+
 mod once_cell;
 
 pub use once_cell::WipeOnForkOnceCell;
@@ -10,10 +17,103 @@ pub use once_lock::WipeOnForkOnceLock;
 mod lazy_lock;
 pub use lazy_lock::WipeOnForkLazyLock;
 
+mod macros;
+/// Requires the `macros` feature.
+///
+/// ```
+/// use wipe_on_fork::wipe_on_fork;
+///
+/// #[wipe_on_fork]
+/// static PID: u32 = std::process::id();
+///
+/// assert_eq!(*PID, std::process::id());
+/// ```
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use wipe_on_fork::wipe_on_fork;
+///
+/// #[wipe_on_fork(policy = "reinit", on_wipe = "forget")]
+/// pub(crate) static CACHE: HashMap<u32, String> = HashMap::new();
+///
+/// assert!(CACHE.is_empty());
+/// ```
+#[cfg(feature = "macros")]
+pub use wipe_on_fork_macros::wipe_on_fork;
+
+// Lets the code generated by `#[wipe_on_fork]` refer to `::wipe_on_fork` within this crate too.
+extern crate self as wipe_on_fork;
+
+#[cfg(feature = "std")]
+mod mutex;
+#[cfg(feature = "std")]
+pub use mutex::WipeOnForkMutex;
+#[cfg(feature = "std")]
+mod rwlock;
+#[cfg(feature = "std")]
+pub use rwlock::WipeOnForkRwLock;
+
+#[cfg(feature = "std")]
+mod thread_local;
+#[cfg(feature = "std")]
+pub use thread_local::WipeOnForkThreadLocal;
+
+#[cfg(feature = "std")]
+mod map;
+#[cfg(feature = "std")]
+pub use map::WipeOnForkMap;
+
+#[cfg(all(unix, feature = "std"))]
+mod fd;
+#[cfg(all(unix, feature = "std"))]
+pub use fd::{set_cloexec, FdPolicy, WipeOnForkFd};
+
+#[cfg(feature = "std")]
+mod async_once_cell;
+#[cfg(feature = "std")]
+pub use async_once_cell::WipeOnForkAsyncOnceCell;
+
+mod policy;
+pub use policy::{ForkPolicy, PoisonPolicy};
+
 mod once;
 pub use once::{WipeOnForkOnce, WIPE_ON_FORK_ONCE_INIT};
 
+#[cfg(all(target_os = "linux", feature = "std"))]
+mod page;
+#[cfg(all(target_os = "linux", feature = "std"))]
+pub use page::WipeOnForkPage;
+
+#[cfg(feature = "rand")]
+mod rng;
+#[cfg(feature = "rand")]
+pub use rng::WipeOnForkRng;
+
+#[cfg(feature = "tokio")]
+mod runtime;
+#[cfg(feature = "tokio")]
+pub use runtime::WipeOnForkRuntime;
+
+#[cfg(feature = "rayon")]
+mod thread_pool;
+#[cfg(feature = "rayon")]
+pub use thread_pool::WipeOnForkThreadPool;
+
+#[cfg(feature = "zeroize")]
+mod secret;
+#[cfg(feature = "zeroize")]
+pub use secret::{WipeOnForkSecretCell, WipeOnForkSecretLock};
+
+pub mod fork;
+
+mod futex;
+
 mod utils;
+pub use utils::{
+    fork_detection, set_fork_detection, set_fork_detector, AtForkDetector, ForkDetection,
+    ForkDetector, PidDetector,
+};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,37 @@
+/// Declares lazily initialized statics with the syntax of `lazy_static!`, as
+/// [`WipeOnForkLazyLock`](crate::WipeOnForkLazyLock)s.
+///
+/// Attributes, doc comments, and visibilities are kept, so existing `lazy_static!` blocks can
+/// be migrated by renaming the macro. Each `static ref NAME: T = expr;` becomes
+/// `static NAME: WipeOnForkLazyLock<T>`, which dereferences to `T` and is initialized again in
+/// each child process.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use wipe_on_fork::wipe_on_fork_lazy_static;
+///
+/// wipe_on_fork_lazy_static! {
+///     /// The PID of the process that first used it.
+///     pub static ref PID: u32 = std::process::id();
+///
+///     pub(crate) static ref NAMES: HashMap<u32, &'static str> = {
+///         let mut m = HashMap::new();
+///         m.insert(13, "Spica");
+///         m
+///     };
+/// }
+///
+/// assert_eq!(*PID, std::process::id());
+/// assert_eq!(NAMES.get(&13), Some(&"Spica"));
+/// ```
+#[macro_export]
+macro_rules! wipe_on_fork_lazy_static {
+    ($(#[$attr:meta])* $vis:vis static ref $name:ident : $ty:ty = $init:expr; $($rest:tt)*) => {
+        $(#[$attr])*
+        $vis static $name: $crate::WipeOnForkLazyLock<$ty> =
+            $crate::WipeOnForkLazyLock::new(|| $init);
+        $crate::wipe_on_fork_lazy_static!($($rest)*);
+    };
+    () => {};
+}
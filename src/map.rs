@@ -0,0 +1,143 @@
+use crate::WipeOnForkMutex;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{MutexGuard, PoisonError};
+
+/// A `HashMap` cache shared by all the threads, which is empty in each new process.
+///
+/// This replaces `LazyLock<Mutex<HashMap<K, V>>>` for memoization caches whose entries are only
+/// valid in the process that created them, such as DNS results, prepared statements, or
+/// per-connection state. The first access in a child process finds an empty map, and the
+/// entries inherited from the parent are leaked rather than dropped (see [`WipeOnForkMutex`]).
+///
+/// Values are returned by cloning them, so they are typically cheap to clone, e.g., `Arc`s.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use wipe_on_fork::WipeOnForkMap;
+///
+/// static RESOLVED: WipeOnForkMap<String, Arc<Vec<u8>>> = WipeOnForkMap::new();
+///
+/// let addr = RESOLVED.get_or_insert_with("localhost".to_string(), || Arc::new(vec![127, 0, 0, 1]));
+/// assert_eq!(*addr, vec![127, 0, 0, 1]);
+/// assert_eq!(RESOLVED.len(), 1);
+/// ```
+pub struct WipeOnForkMap<K, V> {
+    map: WipeOnForkMutex<HashMap<K, V>>,
+}
+
+impl<K, V> WipeOnForkMap<K, V> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> WipeOnForkMap<K, V> {
+        WipeOnForkMap {
+            map: WipeOnForkMutex::new(HashMap::new),
+        }
+    }
+
+    // A panic while the lock is held cannot leave the map half-updated, so poisoning is ignored.
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, HashMap<K, V>> {
+        self.map.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Removes all the entries of the current process.
+    #[inline]
+    pub fn clear(&self) {
+        self.lock().clear()
+    }
+
+    /// Runs `f` with exclusive access to the map of the current process.
+    #[inline]
+    pub fn with<F, U>(&self, f: F) -> U
+    where
+        F: FnOnce(&mut HashMap<K, V>) -> U,
+    {
+        f(&mut self.lock())
+    }
+}
+
+impl<K: Eq + Hash, V> WipeOnForkMap<K, V> {
+    #[inline]
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.lock().insert(key, value)
+    }
+
+    #[inline]
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock().remove(key)
+    }
+
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock().contains_key(key)
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> WipeOnForkMap<K, V> {
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock().get(key).cloned()
+    }
+
+    /// Returns the value for `key`, inserting the one computed by `f` if there is none.
+    ///
+    /// `f` runs without holding the lock, so that a slow computation does not block the other
+    /// keys, and so that it may access the map. If several threads compute the same key at the
+    /// same time, the first value inserted is kept and returned to all of them.
+    ///
+    /// ```
+    /// use wipe_on_fork::WipeOnForkMap;
+    ///
+    /// let map: WipeOnForkMap<u32, u32> = WipeOnForkMap::new();
+    /// assert_eq!(map.get_or_insert_with(1, || 10), 10);
+    /// assert_eq!(map.get_or_insert_with(1, || unreachable!()), 10);
+    /// ```
+    pub fn get_or_insert_with<F>(&self, key: K, f: F) -> V
+    where
+        F: FnOnce() -> V,
+    {
+        if let Some(value) = self.lock().get(&key) {
+            return value.clone();
+        }
+        let value = f();
+        self.lock().entry(key).or_insert(value).clone()
+    }
+}
+
+impl<K, V> Default for WipeOnForkMap<K, V> {
+    #[inline]
+    fn default() -> WipeOnForkMap<K, V> {
+        WipeOnForkMap::new()
+    }
+}
+
+impl<K: core::fmt::Debug, V: core::fmt::Debug> core::fmt::Debug for WipeOnForkMap<K, V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("WipeOnForkMap").field(&*self.lock()).finish()
+    }
+}
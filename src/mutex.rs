@@ -0,0 +1,117 @@
+use crate::{ForkPolicy, WipeOnForkOnceLock};
+use std::sync::{LockResult, Mutex, MutexGuard, TryLockResult};
+
+/// A `Mutex` that is reset in the child process, together with the data that it protects.
+///
+/// A fork that happens while another thread holds a `std::sync::Mutex` leaves the child with a
+/// lock that nobody can release, since that thread does not exist in the child. This mutex is
+/// created from `init` on the first use in each process instead, so the child always starts
+/// with an unlocked mutex and fresh data.
+///
+/// The data inherited from the parent is leaked rather than dropped, as another thread may have
+/// been in the middle of changing it (see [`ForkPolicy::Forget`]). The inherited mutex is boxed, so
+/// it stays where it is, and a guard that the forking thread holds across the fork keeps referring
+/// to it rather than to the child's new mutex.
+///
+/// ```
+/// use wipe_on_fork::WipeOnForkMutex;
+///
+/// static QUEUE: WipeOnForkMutex<Vec<u32>> = WipeOnForkMutex::new(Vec::new);
+///
+/// QUEUE.lock().unwrap().push(1);
+/// assert_eq!(*QUEUE.lock().unwrap(), vec![1]);
+/// ```
+pub struct WipeOnForkMutex<T, F = fn() -> T> {
+    inner: WipeOnForkOnceLock<Box<Mutex<T>>>,
+    init: F,
+}
+
+impl<T, F: Fn() -> T> WipeOnForkMutex<T, F> {
+    #[inline]
+    pub const fn new(init: F) -> WipeOnForkMutex<T, F> {
+        WipeOnForkMutex {
+            inner: WipeOnForkOnceLock::with_policy(ForkPolicy::Forget),
+            init,
+        }
+    }
+
+    #[inline]
+    fn mutex(&self) -> &Mutex<T> {
+        self.inner
+            .get_or_init(|| Box::new(Mutex::new((self.init)())))
+    }
+
+    /// Acquires the mutex, blocking the current thread until it is able to do so. In a child
+    /// process, the first call creates a new, unlocked mutex.
+    #[inline]
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+        self.mutex().lock()
+    }
+
+    /// ```
+    /// use wipe_on_fork::WipeOnForkMutex;
+    ///
+    /// let mutex = WipeOnForkMutex::new(|| 1u32);
+    ///
+    /// let guard = mutex.lock().unwrap();
+    /// assert!(mutex.try_lock().is_err());
+    /// drop(guard);
+    /// assert_eq!(*mutex.try_lock().unwrap(), 1);
+    /// ```
+    #[inline]
+    pub fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+        self.mutex().try_lock()
+    }
+
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.inner.get().is_some_and(|mutex| mutex.is_poisoned())
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        self.mutex();
+        self.inner.get_mut().unwrap().get_mut()
+    }
+
+    /// ```
+    /// use wipe_on_fork::WipeOnForkMutex;
+    ///
+    /// let mutex = WipeOnForkMutex::new(|| 1u32);
+    /// *mutex.lock().unwrap() += 1;
+    /// assert_eq!(mutex.into_inner().unwrap(), 2);
+    /// ```
+    #[inline]
+    pub fn into_inner(mut self) -> LockResult<T> {
+        match self.inner.take() {
+            Some(mutex) => (*mutex).into_inner(),
+            None => Ok((self.init)()),
+        }
+    }
+}
+
+impl<T, F> Drop for WipeOnForkMutex<T, F> {
+    fn drop(&mut self) {
+        // The inherited data is leaked even if the mutex is dropped before its first use in the
+        // child.
+        self.inner.discard_inherited();
+    }
+}
+
+impl<T: Default> Default for WipeOnForkMutex<T> {
+    #[inline]
+    fn default() -> WipeOnForkMutex<T> {
+        WipeOnForkMutex::new(T::default)
+    }
+}
+
+impl<T: core::fmt::Debug, F> core::fmt::Debug for WipeOnForkMutex<T, F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_tuple("WipeOnForkMutex");
+        match self.inner.get() {
+            Some(v) => d.field(v),
+            None => d.field(&format_args!("<uninit>")),
+        };
+        d.finish()
+    }
+}
@@ -1,6 +1,7 @@
+use crate::PoisonPolicy;
 use core::cell::Cell;
-use std::panic::{RefUnwindSafe, UnwindSafe};
-use std::sync::Mutex;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
 
 pub enum ExclusiveState {
     Incomplete,
@@ -16,6 +17,118 @@ pub enum State {
     Complete,
 }
 
+// The state and the generation in which it was reached are packed into a single word, so that a
+// single atomic load tells whether the `Once` is completed in the current generation:
+//
+//   bits 0..2   the `State`
+//   bit  2      set while some thread waits for a `Running` state to end
+//   bit  3      set while a `Running` state applies a fork policy rather than initializing
+//   bits 4..    the generation plus one, or zero for none
+const STATE_MASK: u64 = 0b0011;
+const QUEUED: u64 = 0b0100;
+const WIPING: u64 = 0b1000;
+const GENERATION_SHIFT: u32 = 4;
+
+const INCOMPLETE: u64 = 0;
+const POISONED: u64 = 1;
+const RUNNING: u64 = 2;
+const COMPLETE: u64 = 3;
+
+impl State {
+    #[inline]
+    const fn from_word(word: u64) -> State {
+        match word & STATE_MASK {
+            INCOMPLETE => State::Incomplete,
+            POISONED => State::Poisoned,
+            RUNNING => State::Running,
+            _ => State::Complete,
+        }
+    }
+
+    #[inline]
+    const fn to_word(self) -> u64 {
+        match self {
+            State::Incomplete => INCOMPLETE,
+            State::Poisoned => POISONED,
+            State::Running => RUNNING,
+            State::Complete => COMPLETE,
+        }
+    }
+}
+
+// Numbers the threads in the order in which they first need an ID. An ID is never reused, even
+// by a thread of a child process, which only inherits the ID of the forking thread.
+static NEXT_THREAD: AtomicUsize = AtomicUsize::new(1);
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static THREAD: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Returns a nonzero number that identifies the current thread.
+#[cfg(feature = "std")]
+#[inline]
+fn current_thread() -> usize {
+    THREAD.with(|thread| {
+        if thread.get() == 0 {
+            thread.set(NEXT_THREAD.fetch_add(1, Ordering::Relaxed));
+        }
+        thread.get()
+    })
+}
+
+// Without `std`, the ID is kept as the value of a `pthread` key, created on first use.
+#[cfg(not(feature = "std"))]
+fn current_thread() -> usize {
+    static KEY: AtomicUsize = AtomicUsize::new(0);
+
+    let mut key = KEY.load(Ordering::Acquire);
+    if key == 0 {
+        let mut new_key: libc::pthread_key_t = 0;
+        let res = unsafe { libc::pthread_key_create(&mut new_key, None) };
+        assert_eq!(res, 0, "failed to create a pthread key");
+        // The key is stored plus one, since zero is a valid key.
+        key = match KEY.compare_exchange(
+            0,
+            new_key as usize + 1,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => new_key as usize + 1,
+            Err(existing) => {
+                unsafe { libc::pthread_key_delete(new_key) };
+                existing
+            }
+        };
+    }
+
+    let key = (key - 1) as libc::pthread_key_t;
+    let thread = unsafe { libc::pthread_getspecific(key) } as usize;
+    if thread != 0 {
+        return thread;
+    }
+    let thread = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+    unsafe { libc::pthread_setspecific(key, thread as *const libc::c_void) };
+    thread
+}
+
+#[inline]
+const fn pack(state: State, generation_id: Option<u64>) -> u64 {
+    let generation_bits = match generation_id {
+        None => 0,
+        Some(generation_id) => (generation_id + 1) << GENERATION_SHIFT,
+    };
+    state.to_word() | generation_bits
+}
+
+#[inline]
+const fn generation_of(word: u64) -> Option<u64> {
+    match word >> GENERATION_SHIFT {
+        0 => None,
+        generation_bits => Some(generation_bits - 1),
+    }
+}
+
 /// ```
 /// use wipe_on_fork::WipeOnForkOnce;
 ///
@@ -26,8 +139,14 @@ pub enum State {
 /// });
 /// ```
 pub struct WipeOnForkOnce {
-    generation_id: Mutex<Option<u64>>,
-    state: Mutex<State>,
+    word: AtomicU64,
+    // Bumped every time a `Running` state with waiters ends. Waiters sleep on it, since futexes
+    // are 32-bit.
+    wakeups: AtomicU32,
+    // The thread that holds the `Running` state, or zero, so that it can tell a recursive call
+    // from another thread's initialization, which it would wait for forever.
+    owner: AtomicUsize,
+    poison_policy: PoisonPolicy,
 }
 
 impl UnwindSafe for WipeOnForkOnce {}
@@ -40,6 +159,7 @@ impl RefUnwindSafe for WipeOnForkOnce {}
 ///
 /// static START: WipeOnForkOnce = WIPE_ON_FORK_ONCE_INIT;
 /// ```
+#[allow(clippy::declare_interior_mutable_const)]
 pub const WIPE_ON_FORK_ONCE_INIT: WipeOnForkOnce = WipeOnForkOnce::new();
 
 pub struct WipeOnForkOnceState {
@@ -48,53 +168,223 @@ pub struct WipeOnForkOnceState {
 }
 
 struct CompletionGuard<'a> {
-    generation_id: &'a Mutex<Option<u64>>,
-    state: &'a Mutex<State>,
+    once: &'a WipeOnForkOnce,
     set_state_on_drop_to: State,
-    set_generation_id_on_drop_to: Option<u64>,
 }
 
 impl<'a> Drop for CompletionGuard<'a> {
     fn drop(&mut self) {
-        let mut lock = self.state.lock().unwrap();
-        *lock = self.set_state_on_drop_to;
-
-        let mut lock = self.generation_id.lock().unwrap();
-        *lock = self.set_generation_id_on_drop_to;
+        let state = self.set_state_on_drop_to;
+        self.once
+            .finish(pack(state, self.once.generation_to_record(state)));
     }
 }
 
-unsafe impl Sync for WipeOnForkOnce {}
-
 impl WipeOnForkOnce {
-    #[cfg(unix)]
     #[inline]
     fn wipe_if_should_wipe(&self) {
-        let mut lock = self.generation_id.lock().unwrap();
+        self.wipe_if_should_wipe_with(|| false);
+    }
 
-        let res = match *lock {
+    /// Runs `on_wipe` if the `Once` was completed (or poisoned) in an earlier generation, before
+    /// resetting it. `on_wipe` returns whether the value protected by the `Once` is still
+    /// initialized in the current generation, in which case the `Once` becomes completed.
+    ///
+    /// Other threads wait for `on_wipe` to finish. It must not access the same `Once`.
+    #[cfg(unix)]
+    #[inline]
+    pub(crate) fn wipe_if_should_wipe_with<F>(&self, on_wipe: F)
+    where
+        F: FnOnce() -> bool,
+    {
+        let word = self.word.load(Ordering::Acquire);
+        let res = match generation_of(word) {
             None => false,
-            Some(generation_id) => generation_id != crate::utils::GENERATION.get(),
+            Some(generation_id) => generation_id != crate::utils::generation(),
         };
 
-        if res {
-            *lock = None;
-            *self.state.lock().unwrap() = State::Incomplete;
+        if res || word & WIPING != 0 {
+            self.wipe(on_wipe);
+        }
+    }
+
+    #[cfg(unix)]
+    #[cold]
+    fn wipe<F>(&self, on_wipe: F)
+    where
+        F: FnOnce() -> bool,
+    {
+        loop {
+            let word = self.word.load(Ordering::Acquire);
+            let current = crate::utils::generation();
+            match generation_of(word) {
+                Some(generation_id) if generation_id != current => {}
+                _ => {
+                    if word & WIPING != 0 {
+                        self.wait();
+                        continue;
+                    }
+                    return;
+                }
+            }
+
+            if word & STATE_MASK == RUNNING {
+                // The `Running` state was inherited from a thread of the parent, which does not
+                // exist here to finish it, unless it is the current thread, which forked from
+                // within the initializer. Whatever the initializer had written is not trusted, so
+                // it is not given to `on_wipe`, and the next initializer overwrites it.
+                if self.owner.load(Ordering::Relaxed) == current_thread() {
+                    return;
+                }
+                if self
+                    .word
+                    .compare_exchange(
+                        word,
+                        pack(State::Incomplete, None),
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+                {
+                    self.owner.store(0, Ordering::Relaxed);
+                }
+                continue;
+            }
+
+            // The state is finished, so nobody is waiting on `word`.
+            if self
+                .word
+                .compare_exchange(
+                    word,
+                    pack(State::Running, Some(current)) | WIPING,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+            {
+                continue;
+            }
+
+            self.owner.store(current_thread(), Ordering::Relaxed);
+            let mut guard = CompletionGuard {
+                once: self,
+                set_state_on_drop_to: self.poisoned_state(),
+            };
+            if on_wipe() {
+                guard.set_state_on_drop_to = State::Complete;
+            } else {
+                guard.set_state_on_drop_to = State::Incomplete;
+            }
+            return;
         }
     }
 
     #[cfg(not(unix))]
     #[inline]
-    fn wipe_if_should_wipe(&self) {}
+    pub(crate) fn wipe_if_should_wipe_with<F>(&self, _on_wipe: F)
+    where
+        F: FnOnce() -> bool,
+    {
+    }
+
+    /// Blocks until the `Once` leaves the `Running` state, or returns right away if it is not in
+    /// it. It may also return spuriously.
+    ///
+    /// Panics if the current thread is the one in the `Running` state.
+    #[cold]
+    fn wait(&self) {
+        if self.owner.load(Ordering::Relaxed) == current_thread() {
+            panic!("one-time initialization may not be performed recursively");
+        }
+
+        // `wakeups` is read before `word`, so that a `finish` in between makes the futex wait
+        // return immediately instead of missing the wakeup.
+        let wakeups = self.wakeups.load(Ordering::Acquire);
+        let word = self.word.load(Ordering::Acquire);
+        if word & STATE_MASK != RUNNING {
+            return;
+        }
+        if word & QUEUED == 0
+            && self
+                .word
+                .compare_exchange(word, word | QUEUED, Ordering::Acquire, Ordering::Acquire)
+                .is_err()
+        {
+            return;
+        }
+        crate::futex::wait(&self.wakeups, wakeups);
+    }
+
+    /// Leaves the `Running` state for `word`, and wakes up the threads waiting for that.
+    #[inline]
+    fn finish(&self, word: u64) {
+        self.owner.store(0, Ordering::Relaxed);
+        let prev = self.word.swap(word, Ordering::AcqRel);
+        if prev & QUEUED != 0 {
+            self.wakeups.fetch_add(1, Ordering::Release);
+            crate::futex::wake_all(&self.wakeups);
+        }
+    }
 
     #[inline]
     pub const fn new() -> WipeOnForkOnce {
+        WipeOnForkOnce::with_poison_policy(PoisonPolicy::Poison)
+    }
+
+    /// Creates a new `Once` with the given policy for initializers that panic.
+    ///
+    /// ```
+    /// use wipe_on_fork::{PoisonPolicy, WipeOnForkOnce};
+    ///
+    /// static INIT: WipeOnForkOnce = WipeOnForkOnce::with_poison_policy(PoisonPolicy::Retry);
+    ///
+    /// let handle = std::thread::spawn(|| {
+    ///     INIT.call_once(|| panic!());
+    /// });
+    /// assert!(handle.join().is_err());
+    /// assert!(!INIT.is_poisoned());
+    ///
+    /// INIT.call_once(|| {});
+    /// assert!(INIT.is_completed());
+    /// ```
+    #[inline]
+    pub const fn with_poison_policy(poison_policy: PoisonPolicy) -> WipeOnForkOnce {
         WipeOnForkOnce {
-            generation_id: Mutex::new(None),
-            state: Mutex::new(State::Incomplete),
+            word: AtomicU64::new(pack(State::Incomplete, None)),
+            wakeups: AtomicU32::new(0),
+            owner: AtomicUsize::new(0),
+            poison_policy,
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn poison_policy(&self) -> PoisonPolicy {
+        self.poison_policy
+    }
+
+    /// The state in which an initializer leaves the `Once` if it panics or calls
+    /// `WipeOnForkOnceState::poison`.
+    #[inline]
+    const fn poisoned_state(&self) -> State {
+        match self.poison_policy {
+            PoisonPolicy::Poison | PoisonPolicy::PoisonInherited => State::Poisoned,
+            PoisonPolicy::Retry => State::Incomplete,
+        }
+    }
+
+    /// The generation stored along with a finished state, which makes a fork reset that state in
+    /// the child, or `None` to keep the state in the child.
+    #[inline]
+    fn generation_to_record(&self, state: State) -> Option<u64> {
+        match (state, self.poison_policy) {
+            (State::Incomplete, _) | (State::Poisoned, PoisonPolicy::PoisonInherited) => None,
+            _ => Some(crate::utils::generation()),
         }
     }
 
+    /// If another thread is running an initialization routine, this blocks until it finishes.
+    /// Calling it again from within `f`, on the same thread, panics instead of deadlocking.
+    ///
     /// ```
     /// use wipe_on_fork::WipeOnForkOnce;
     ///
@@ -197,15 +487,35 @@ impl WipeOnForkOnce {
     #[inline]
     pub fn is_completed(&self) -> bool {
         self.wipe_if_should_wipe();
-        let lock = self.state.lock().unwrap();
-        *lock == State::Complete
+        State::from_word(self.word.load(Ordering::Acquire)) == State::Complete
+    }
+
+    /// Returns `true` if an initializer panicked, and no other initializer has completed since.
+    /// This never happens with [`PoisonPolicy::Retry`].
+    ///
+    /// ```
+    /// use wipe_on_fork::WipeOnForkOnce;
+    /// use std::thread;
+    ///
+    /// static INIT: WipeOnForkOnce = WipeOnForkOnce::new();
+    ///
+    /// assert!(!INIT.is_poisoned());
+    /// let handle = thread::spawn(|| {
+    ///     INIT.call_once(|| panic!());
+    /// });
+    /// assert!(handle.join().is_err());
+    /// assert!(INIT.is_poisoned());
+    /// ```
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.wipe_if_should_wipe();
+        State::from_word(self.word.load(Ordering::Acquire)) == State::Poisoned
     }
 
     #[inline]
     pub fn state(&mut self) -> ExclusiveState {
         self.wipe_if_should_wipe();
-        let lock = self.state.lock().unwrap();
-        match *lock {
+        match State::from_word(*self.word.get_mut()) {
             State::Incomplete => ExclusiveState::Incomplete,
             State::Poisoned => ExclusiveState::Poisoned,
             State::Complete => ExclusiveState::Complete,
@@ -215,41 +525,61 @@ impl WipeOnForkOnce {
 
     #[cold]
     pub(crate) fn _call(&self, ignore_poisoning: bool, f: &mut impl FnMut(&WipeOnForkOnceState)) {
-        self.wipe_if_should_wipe();
+        loop {
+            self.wipe_if_should_wipe();
 
-        let cur_state: State = {
-            let lock = self.state.lock().unwrap();
-            lock.clone()
-        };
-        match cur_state {
-            State::Poisoned if !ignore_poisoning => {
-                panic!("WipeOnForkOnce instance has previously been poisoned");
-            }
-            State::Incomplete | State::Poisoned => {
-                *self.state.lock().unwrap() = State::Running;
-
-                let mut guard = CompletionGuard {
-                    generation_id: &self.generation_id,
-                    state: &self.state,
-                    set_state_on_drop_to: State::Poisoned,
-                    set_generation_id_on_drop_to: None,
-                };
-                let f_state = WipeOnForkOnceState {
-                    poisoned: cur_state == State::Poisoned,
-                    set_state_to: Cell::new(State::Complete),
-                };
-                f(&f_state);
-                guard.set_state_on_drop_to = f_state.set_state_to.get();
-                guard.set_generation_id_on_drop_to = Some(crate::utils::GENERATION.get());
-            }
-            State::Running => {
-                panic!("one-time initialization may not be performed recursively");
+            let word = self.word.load(Ordering::Acquire);
+            let cur_state = State::from_word(word);
+            match cur_state {
+                State::Poisoned if !ignore_poisoning => {
+                    panic!("WipeOnForkOnce instance has previously been poisoned");
+                }
+                State::Incomplete | State::Poisoned => {
+                    // The `Running` state carries the generation in which it started, so that a
+                    // child process forked in the meantime can tell it apart from its own.
+                    if self
+                        .word
+                        .compare_exchange(
+                            word,
+                            pack(State::Running, Some(crate::utils::generation())),
+                            Ordering::Acquire,
+                            Ordering::Relaxed,
+                        )
+                        .is_err()
+                    {
+                        continue;
+                    }
+
+                    self.owner.store(current_thread(), Ordering::Relaxed);
+                    let mut guard = CompletionGuard {
+                        once: self,
+                        set_state_on_drop_to: self.poisoned_state(),
+                    };
+                    let f_state = WipeOnForkOnceState {
+                        poisoned: cur_state == State::Poisoned,
+                        set_state_to: Cell::new(State::Complete),
+                    };
+                    f(&f_state);
+                    guard.set_state_on_drop_to = match f_state.set_state_to.get() {
+                        State::Poisoned => self.poisoned_state(),
+                        state => state,
+                    };
+                    return;
+                }
+                State::Running => self.wait(),
+                State::Complete => return,
             }
-            State::Complete => {}
         }
     }
 }
 
+impl Default for WipeOnForkOnce {
+    #[inline]
+    fn default() -> WipeOnForkOnce {
+        WipeOnForkOnce::new()
+    }
+}
+
 impl core::fmt::Debug for WipeOnForkOnce {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("WipeOnForkOnce").finish_non_exhaustive()
@@ -297,6 +627,12 @@ impl WipeOnForkOnceState {
     pub fn poison(&self) {
         self.set_state_to.set(State::Poisoned)
     }
+
+    /// Leaves the `Once` incomplete rather than completed, without poisoning it.
+    #[inline]
+    pub(crate) fn set_incomplete(&self) {
+        self.set_state_to.set(State::Incomplete)
+    }
 }
 
 impl core::fmt::Debug for WipeOnForkOnceState {
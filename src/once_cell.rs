@@ -1,5 +1,6 @@
-use std::cell::{Cell, UnsafeCell};
-use std::marker::PhantomData;
+use crate::ForkPolicy;
+use core::cell::{Cell, UnsafeCell};
+use core::marker::PhantomData;
 
 /// ```
 /// use wipe_on_fork::WipeOnForkOnceCell;
@@ -16,6 +17,7 @@ use std::marker::PhantomData;
 pub struct WipeOnForkOnceCell<T> {
     generation_id: Cell<Option<u64>>,
     inner: UnsafeCell<Option<T>>,
+    policy: ForkPolicy<T>,
     _not_send_sync: PhantomData<*const ()>,
 }
 
@@ -23,10 +25,10 @@ impl<T> WipeOnForkOnceCell<T> {
     #[cfg(unix)]
     #[inline]
     fn check_if_should_wipe(&self) -> bool {
-        return match self.generation_id.get() {
+        match self.generation_id.get() {
             None => false,
-            Some(generation_id) => generation_id != crate::utils::GENERATION.get(),
-        };
+            Some(generation_id) => generation_id != crate::utils::generation(),
+        }
     }
 
     #[cfg(not(unix))]
@@ -39,8 +41,8 @@ impl<T> WipeOnForkOnceCell<T> {
     fn wipe_if_should_wipe(&self) {
         if self.check_if_should_wipe() {
             self.generation_id.set(None);
-            unsafe {
-                *self.inner.get() = None;
+            if self.policy.apply(unsafe { &mut *self.inner.get() }) {
+                self.generation_id.set(Some(crate::utils::generation()));
             }
         }
     }
@@ -48,9 +50,24 @@ impl<T> WipeOnForkOnceCell<T> {
     #[inline]
     #[must_use]
     pub const fn new() -> Self {
+        WipeOnForkOnceCell::with_policy(ForkPolicy::Wipe)
+    }
+
+    /// Creates a new cell with the given policy for the value inherited from the parent.
+    ///
+    /// ```
+    /// use wipe_on_fork::{ForkPolicy, WipeOnForkOnceCell};
+    ///
+    /// // A child must not close the parent's file descriptor.
+    /// let cell: WipeOnForkOnceCell<std::fs::File> = WipeOnForkOnceCell::with_policy(ForkPolicy::Forget);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_policy(policy: ForkPolicy<T>) -> Self {
         WipeOnForkOnceCell {
             generation_id: Cell::new(None),
             inner: UnsafeCell::new(None),
+            policy,
             _not_send_sync: PhantomData,
         }
     }
@@ -107,7 +124,7 @@ impl<T> WipeOnForkOnceCell<T> {
             return Err((old, value));
         }
 
-        self.generation_id.set(Some(crate::utils::GENERATION.get()));
+        self.generation_id.set(Some(crate::utils::generation()));
 
         let slot = unsafe { &mut *self.inner.get() };
         Ok(slot.insert(value))
@@ -199,7 +216,9 @@ impl<T> WipeOnForkOnceCell<T> {
     /// ```
     #[inline]
     pub fn take(&mut self) -> Option<T> {
-        core::mem::take(self).into_inner()
+        self.wipe_if_should_wipe();
+        self.generation_id.set(None);
+        self.inner.get_mut().take()
     }
 }
 
@@ -224,7 +243,7 @@ impl<T: core::fmt::Debug> core::fmt::Debug for WipeOnForkOnceCell<T> {
 impl<T: Clone> Clone for WipeOnForkOnceCell<T> {
     #[inline]
     fn clone(&self) -> WipeOnForkOnceCell<T> {
-        let res = WipeOnForkOnceCell::new();
+        let res = WipeOnForkOnceCell::with_policy(self.policy);
         if let Some(value) = self.get() {
             match res.set(value.clone()) {
                 Ok(()) => (),
@@ -249,8 +268,9 @@ impl<T> From<T> for WipeOnForkOnceCell<T> {
     #[inline]
     fn from(value: T) -> Self {
         WipeOnForkOnceCell {
-            generation_id: Cell::new(Some(crate::utils::GENERATION.get())),
+            generation_id: Cell::new(Some(crate::utils::generation())),
             inner: UnsafeCell::new(Some(value)),
+            policy: ForkPolicy::Wipe,
             _not_send_sync: PhantomData,
         }
     }
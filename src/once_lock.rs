@@ -1,7 +1,7 @@
-use crate::WipeOnForkOnce;
-use std::cell::UnsafeCell;
-use std::marker::PhantomData;
-use std::panic::{RefUnwindSafe, UnwindSafe};
+use crate::{ForkPolicy, PoisonPolicy, WipeOnForkOnce};
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::panic::{RefUnwindSafe, UnwindSafe};
 
 /// ```
 /// use wipe_on_fork::WipeOnForkOnceLock;
@@ -23,6 +23,7 @@ use std::panic::{RefUnwindSafe, UnwindSafe};
 pub struct WipeOnForkOnceLock<T> {
     once: WipeOnForkOnce,
     value: UnsafeCell<Option<T>>,
+    policy: ForkPolicy<T>,
     _marker: PhantomData<T>,
 }
 
@@ -30,9 +31,48 @@ impl<T> WipeOnForkOnceLock<T> {
     #[inline]
     #[must_use]
     pub const fn new() -> WipeOnForkOnceLock<T> {
+        WipeOnForkOnceLock::with_policy(ForkPolicy::Wipe)
+    }
+
+    /// Creates a new cell with the given policy for the value inherited from the parent.
+    ///
+    /// ```
+    /// use wipe_on_fork::{ForkPolicy, WipeOnForkOnceLock};
+    ///
+    /// static CELL: WipeOnForkOnceLock<u32> =
+    ///     WipeOnForkOnceLock::with_policy(ForkPolicy::Reinit(std::process::id));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_policy(policy: ForkPolicy<T>) -> WipeOnForkOnceLock<T> {
+        WipeOnForkOnceLock::with_policies(policy, PoisonPolicy::Poison)
+    }
+
+    /// Creates a new cell with the given policies for the value inherited from the parent, and
+    /// for initializers that panic.
+    ///
+    /// ```
+    /// use wipe_on_fork::{ForkPolicy, PoisonPolicy, WipeOnForkOnceLock};
+    ///
+    /// static CELL: WipeOnForkOnceLock<u32> =
+    ///     WipeOnForkOnceLock::with_policies(ForkPolicy::Wipe, PoisonPolicy::Retry);
+    ///
+    /// let handle = std::thread::spawn(|| {
+    ///     CELL.get_or_init(|| panic!());
+    /// });
+    /// assert!(handle.join().is_err());
+    /// assert!(!CELL.is_poisoned());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_policies(
+        policy: ForkPolicy<T>,
+        poison_policy: PoisonPolicy,
+    ) -> WipeOnForkOnceLock<T> {
         WipeOnForkOnceLock {
-            once: WipeOnForkOnce::new(),
+            once: WipeOnForkOnce::with_poison_policy(poison_policy),
             value: UnsafeCell::new(None),
+            policy,
             _marker: PhantomData,
         }
     }
@@ -125,6 +165,9 @@ impl<T> WipeOnForkOnceLock<T> {
         }
     }
 
+    /// If `f` returns an error, the cell is left uninitialized, as if `f` had never run, and a
+    /// thread that was waiting for `f` runs its own initializer.
+    ///
     /// ```
     /// use wipe_on_fork::WipeOnForkOnceLock;
     ///
@@ -181,7 +224,7 @@ impl<T> WipeOnForkOnceLock<T> {
     #[inline]
     pub fn take(&mut self) -> Option<T> {
         if self.is_initialized() {
-            self.once = WipeOnForkOnce::new();
+            self.once = WipeOnForkOnce::with_poison_policy(self.once.poison_policy());
             unsafe { (&mut *self.value.get()).take() }
         } else {
             None
@@ -190,9 +233,47 @@ impl<T> WipeOnForkOnceLock<T> {
 
     #[inline]
     pub fn is_initialized(&self) -> bool {
+        self.once
+            .wipe_if_should_wipe_with(|| self.policy.apply(unsafe { &mut *self.value.get() }));
         self.once.is_completed()
     }
 
+    /// Discards a value inherited from the parent as [`ForkPolicy::discard`] does, for the types
+    /// that are built on this cell and implement `Drop`.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn discard_inherited(&self) {
+        self.once.wipe_if_should_wipe_with(|| {
+            let slot = unsafe { &mut *self.value.get() };
+            self.policy.discard(slot);
+            slot.is_some()
+        });
+    }
+
+    /// Returns `true` if the last attempt to initialize the cell panicked. Unlike
+    /// `WipeOnForkOnce`, the cell does not refuse to be initialized again in that case. An
+    /// initializer that returns an error does not poison the cell.
+    ///
+    /// ```
+    /// use wipe_on_fork::WipeOnForkOnceLock;
+    ///
+    /// static CELL: WipeOnForkOnceLock<u32> = WipeOnForkOnceLock::new();
+    ///
+    /// assert_eq!(CELL.get_or_try_init(|| Err(())), Err(()));
+    /// assert!(!CELL.is_poisoned());
+    ///
+    /// assert!(std::thread::spawn(|| CELL.get_or_init(|| panic!())).join().is_err());
+    /// assert!(CELL.is_poisoned());
+    /// assert_eq!(CELL.get_or_init(|| 92), &92);
+    /// assert!(!CELL.is_poisoned());
+    /// ```
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.once
+            .wipe_if_should_wipe_with(|| self.policy.apply(unsafe { &mut *self.value.get() }));
+        self.once.is_poisoned()
+    }
+
     #[cold]
     pub(crate) fn _initialize<F, E>(&self, f: F) -> Result<(), E>
     where
@@ -203,15 +284,17 @@ impl<T> WipeOnForkOnceLock<T> {
 
         self.once.call_once_force(|p| {
             match f() {
+                // The slot is empty, except after an initializer of the parent was interrupted by
+                // a fork, in which case its leftovers are leaked rather than dropped.
                 Ok(value) => unsafe {
-                    *slot.get() = Some(value);
+                    slot.get().write(Some(value));
                 },
                 Err(e) => {
                     res = Err(e);
 
-                    // Treat the underlying `Once` as poisoned since we
-                    // failed to initialize our value. Calls
-                    p.poison();
+                    // Unlike a panic, an error is not a reason to poison the `Once`. It goes
+                    // straight back to incomplete, so that other threads never see it poisoned.
+                    p.set_incomplete();
                 }
             }
         });
@@ -225,9 +308,9 @@ impl<T> WipeOnForkOnceLock<T> {
     }
 
     #[inline]
-    pub(crate) fn get_unchecked_mut(&self) -> &mut T {
+    pub(crate) fn get_unchecked_mut(&mut self) -> &mut T {
         debug_assert!(self.is_initialized());
-        unsafe { (&mut *self.value.get()).as_mut().unwrap() }
+        self.value.get_mut().as_mut().unwrap()
     }
 }
 
@@ -265,7 +348,7 @@ impl<T: core::fmt::Debug> core::fmt::Debug for WipeOnForkOnceLock<T> {
 impl<T: Clone> Clone for WipeOnForkOnceLock<T> {
     #[inline]
     fn clone(&self) -> WipeOnForkOnceLock<T> {
-        let cell = Self::new();
+        let cell = Self::with_policies(self.policy, self.once.poison_policy());
         if let Some(value) = self.get() {
             match cell.set(value.clone()) {
                 Ok(()) => (),
@@ -0,0 +1,315 @@
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+
+// The kernel fills the page with zeros in the child, so `INCOMPLETE` must be zero. While
+// `RUNNING`, the `QUEUED` bit is set once a thread waits on the state with a futex.
+const INCOMPLETE: u32 = 0;
+const RUNNING: u32 = 1;
+const COMPLETE: u32 = 2;
+const QUEUED: u32 = 0b100;
+
+#[repr(C)]
+struct Page<T> {
+    state: AtomicU32,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// A `OnceLock`-like cell whose value lives in memory mapped with `MADV_WIPEONFORK`.
+///
+/// Unlike the other `WipeOnFork*` types, which check a generation counter when they are
+/// accessed, here the kernel presents the child with a zero-filled page, so the parent's value
+/// is gone before any code runs in the child. It is never dropped in the child, and it does not
+/// matter how the child was created (`fork()`, `syscall(SYS_fork)`, `clone()`, ...).
+///
+/// Only the bytes of `T` itself are in the page. Memory that `T` owns elsewhere, such as the
+/// buffer of a `Vec` or a `Box`, is copied into the child as usual, so secrets should be stored
+/// inline, for example as `[u8; 32]`.
+///
+/// This requires Linux 4.14 or newer. The page is mapped on first use, which panics if the
+/// kernel does not support `MADV_WIPEONFORK`.
+///
+/// ```
+/// use wipe_on_fork::WipeOnForkPage;
+///
+/// static SEED: WipeOnForkPage<[u8; 32]> = WipeOnForkPage::new();
+///
+/// let seed = SEED.get_or_init(|| [7u8; 32]);
+/// assert_eq!(seed, &[7u8; 32]);
+/// ```
+pub struct WipeOnForkPage<T> {
+    page: AtomicPtr<Page<T>>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> WipeOnForkPage<T> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> WipeOnForkPage<T> {
+        WipeOnForkPage {
+            page: AtomicPtr::new(core::ptr::null_mut()),
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn mapping_len() -> usize {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        assert!(core::mem::align_of::<Page<T>>() <= page_size);
+        core::mem::size_of::<Page<T>>().div_ceil(page_size) * page_size
+    }
+
+    #[inline]
+    fn page(&self) -> &Page<T> {
+        let page = self.page.load(Ordering::Acquire);
+        if page.is_null() {
+            self.map()
+        } else {
+            unsafe { &*page }
+        }
+    }
+
+    #[cold]
+    fn map(&self) -> &Page<T> {
+        let len = Self::mapping_len();
+        let page = unsafe {
+            let page = libc::mmap(
+                core::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            );
+            if page == libc::MAP_FAILED {
+                panic!(
+                    "WipeOnForkPage failed to map memory: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+            if libc::madvise(page, len, libc::MADV_WIPEONFORK) != 0 {
+                let err = std::io::Error::last_os_error();
+                libc::munmap(page, len);
+                panic!(
+                    "WipeOnForkPage requires MADV_WIPEONFORK (Linux 4.14+): {}",
+                    err
+                );
+            }
+            page as *mut Page<T>
+        };
+
+        match self.page.compare_exchange(
+            core::ptr::null_mut(),
+            page,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => unsafe { &*page },
+            Err(existing) => unsafe {
+                libc::munmap(page as *mut libc::c_void, len);
+                &*existing
+            },
+        }
+    }
+
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        let page = self.page.load(Ordering::Acquire);
+        if page.is_null() {
+            return None;
+        }
+        let page = unsafe { &*page };
+        if page.state.load(Ordering::Acquire) == COMPLETE {
+            Some(unsafe { (*page.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        let page = *self.page.get_mut();
+        if page.is_null() {
+            return None;
+        }
+        let page = unsafe { &mut *page };
+        if *page.state.get_mut() == COMPLETE {
+            Some(unsafe { page.value.get_mut().assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// ```
+    /// use wipe_on_fork::WipeOnForkPage;
+    ///
+    /// static KEY: WipeOnForkPage<[u8; 16]> = WipeOnForkPage::new();
+    ///
+    /// assert_eq!(KEY.set([1u8; 16]), Ok(()));
+    /// assert_eq!(KEY.set([2u8; 16]), Err([2u8; 16]));
+    /// assert_eq!(KEY.get(), Some(&[1u8; 16]));
+    /// ```
+    #[inline]
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        self.get_or_init(|| value.take().unwrap());
+        match value {
+            None => Ok(()),
+            Some(value) => Err(value),
+        }
+    }
+
+    /// Initializing the cell again from within `f` deadlocks.
+    ///
+    /// ```
+    /// use wipe_on_fork::WipeOnForkPage;
+    ///
+    /// let page = WipeOnForkPage::new();
+    /// let value = page.get_or_init(|| 92u64);
+    /// assert_eq!(value, &92);
+    /// let value = page.get_or_init(|| unreachable!());
+    /// assert_eq!(value, &92);
+    /// ```
+    #[inline]
+    pub fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        self.initialize(f)
+    }
+
+    #[cold]
+    fn initialize<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        struct ResetOnPanic<'a> {
+            state: &'a AtomicU32,
+        }
+
+        impl Drop for ResetOnPanic<'_> {
+            fn drop(&mut self) {
+                finish(self.state, INCOMPLETE);
+            }
+        }
+
+        let page = self.page();
+        let mut state = page.state.load(Ordering::Acquire);
+        loop {
+            match state {
+                INCOMPLETE => match page.state.compare_exchange_weak(
+                    INCOMPLETE,
+                    RUNNING,
+                    Ordering::Acquire,
+                    Ordering::Acquire,
+                ) {
+                    Ok(_) => {
+                        let guard = ResetOnPanic { state: &page.state };
+                        let value = f();
+                        core::mem::forget(guard);
+
+                        let value = unsafe { (*page.value.get()).write(value) };
+                        finish(&page.state, COMPLETE);
+                        return value;
+                    }
+                    Err(actual) => state = actual,
+                },
+                COMPLETE => return unsafe { (*page.value.get()).assume_init_ref() },
+                _ => {
+                    // Another thread is initializing the cell. Block until it is done, after
+                    // asking it to wake this thread up.
+                    if state & QUEUED == 0 {
+                        if let Err(actual) = page.state.compare_exchange_weak(
+                            state,
+                            state | QUEUED,
+                            Ordering::Acquire,
+                            Ordering::Acquire,
+                        ) {
+                            state = actual;
+                            continue;
+                        }
+                    }
+                    crate::futex::wait(&page.state, RUNNING | QUEUED);
+                    state = page.state.load(Ordering::Acquire);
+                }
+            }
+        }
+    }
+
+    /// ```
+    /// use wipe_on_fork::WipeOnForkPage;
+    ///
+    /// let mut page = WipeOnForkPage::new();
+    /// assert_eq!(page.take(), None);
+    ///
+    /// page.set([3u8; 8]).unwrap();
+    /// assert_eq!(page.take(), Some([3u8; 8]));
+    /// assert_eq!(page.get(), None);
+    /// ```
+    #[inline]
+    pub fn take(&mut self) -> Option<T> {
+        let page = *self.page.get_mut();
+        if page.is_null() {
+            return None;
+        }
+        let page = unsafe { &mut *page };
+        if *page.state.get_mut() == COMPLETE {
+            *page.state.get_mut() = INCOMPLETE;
+            Some(unsafe { page.value.get_mut().assume_init_read() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Drop for WipeOnForkPage<T> {
+    fn drop(&mut self) {
+        let page = *self.page.get_mut();
+        if page.is_null() {
+            return;
+        }
+        unsafe {
+            if *(*page).state.get_mut() == COMPLETE {
+                (*page).value.get_mut().assume_init_drop();
+            }
+            libc::munmap(page as *mut libc::c_void, Self::mapping_len());
+        }
+    }
+}
+
+/// Leaves the `RUNNING` state for `new`, and wakes up the threads waiting for that.
+#[inline]
+fn finish(state: &AtomicU32, new: u32) {
+    if state.swap(new, Ordering::AcqRel) & QUEUED != 0 {
+        crate::futex::wake_all(state);
+    }
+}
+
+unsafe impl<T: Sync + Send> Sync for WipeOnForkPage<T> {}
+unsafe impl<T: Send> Send for WipeOnForkPage<T> {}
+
+impl<T: RefUnwindSafe + UnwindSafe> RefUnwindSafe for WipeOnForkPage<T> {}
+impl<T: UnwindSafe> UnwindSafe for WipeOnForkPage<T> {}
+
+impl<T> Default for WipeOnForkPage<T> {
+    #[inline]
+    fn default() -> WipeOnForkPage<T> {
+        WipeOnForkPage::new()
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for WipeOnForkPage<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_tuple("WipeOnForkPage");
+        match self.get() {
+            Some(v) => d.field(v),
+            None => d.field(&format_args!("<uninit>")),
+        };
+        d.finish()
+    }
+}
@@ -0,0 +1,137 @@
+/// What the `WipeOnFork*` cells do with a value inherited from the parent.
+///
+/// The policy is applied once, on the first access in the child. While it runs, other threads
+/// accessing the same cell wait for it, and the callbacks must not access the same cell.
+///
+/// The plain cells drop an inherited value that was never accessed along with themselves, so that
+/// they can hold borrowed values. [`WipeOnForkLazyLock`](crate::WipeOnForkLazyLock) and the types
+/// built on the cells, such as the mutexes, only discard it instead: `Forget` leaks it, `Custom`
+/// runs, and any other policy drops it, without computing a new value.
+///
+/// ```
+/// use wipe_on_fork::{ForkPolicy, WipeOnForkOnceLock};
+///
+/// // The child derives its own seed from the parent's.
+/// static SEED: WipeOnForkOnceLock<u64> = WipeOnForkOnceLock::with_policy(ForkPolicy::Transform(
+///     |seed| seed.rotate_left(17) ^ std::process::id() as u64,
+/// ));
+///
+/// SEED.get_or_init(|| 0x9e3779b97f4a7c15);
+/// ```
+pub enum ForkPolicy<T> {
+    /// Discard the inherited value. The cell is uninitialized in the child, and initialized
+    /// again the next time it is needed. This is the default.
+    Wipe,
+    /// Discard the inherited value like `Wipe`, but leak it with [`core::mem::forget`] instead of
+    /// dropping it. This is for values whose `Drop` is wrong to run in the child, such as thread
+    /// pools whose threads do not exist in the child, owners of file descriptors shared with the
+    /// parent, or buffers that the parent will flush too.
+    Forget,
+    /// Keep the inherited value, as the non-wipe-on-fork counterparts would do.
+    Keep,
+    /// Discard the inherited value, and eagerly recompute it with the given function on the
+    /// first access in the child, so that even `get()` finds it initialized.
+    Reinit(fn() -> T),
+    /// Build the child's value from the inherited value.
+    Transform(fn(T) -> T),
+    /// Run the given function on the inherited value. The cell is initialized in the child if,
+    /// and only if, the function leaves `Some` in the slot.
+    Custom(fn(&mut Option<T>)),
+}
+
+impl<T> ForkPolicy<T> {
+    /// Applies the policy to the slot inherited from the parent, and returns whether the slot is
+    /// still initialized.
+    pub(crate) fn apply(&self, slot: &mut Option<T>) -> bool {
+        match *self {
+            ForkPolicy::Wipe => {
+                *slot = None;
+            }
+            ForkPolicy::Forget => {
+                core::mem::forget(slot.take());
+            }
+            ForkPolicy::Keep => {}
+            ForkPolicy::Reinit(f) => {
+                *slot = None;
+                *slot = Some(f());
+            }
+            ForkPolicy::Transform(f) => {
+                if let Some(value) = slot.take() {
+                    *slot = Some(f(value));
+                }
+            }
+            ForkPolicy::Custom(f) => f(slot),
+        }
+        slot.is_some()
+    }
+
+    /// Discards the slot inherited from the parent when its cell is dropped, without computing a
+    /// new value.
+    pub(crate) fn discard(&self, slot: &mut Option<T>) {
+        match *self {
+            ForkPolicy::Forget => core::mem::forget(slot.take()),
+            ForkPolicy::Custom(f) => f(slot),
+            _ => *slot = None,
+        }
+    }
+}
+
+impl<T> Clone for ForkPolicy<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ForkPolicy<T> {}
+
+impl<T> Default for ForkPolicy<T> {
+    #[inline]
+    fn default() -> Self {
+        ForkPolicy::Wipe
+    }
+}
+
+impl<T> core::fmt::Debug for ForkPolicy<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ForkPolicy::Wipe => f.write_str("Wipe"),
+            ForkPolicy::Forget => f.write_str("Forget"),
+            ForkPolicy::Keep => f.write_str("Keep"),
+            ForkPolicy::Reinit(_) => f.write_str("Reinit"),
+            ForkPolicy::Transform(_) => f.write_str("Transform"),
+            ForkPolicy::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// What a [`WipeOnForkOnce`](crate::WipeOnForkOnce), and the locks built on it, do when an
+/// initializer panics.
+///
+/// ```
+/// use wipe_on_fork::{ForkPolicy, PoisonPolicy, WipeOnForkLazyLock};
+///
+/// // Retried on the next access if the server is not up yet.
+/// static ADDRESS: WipeOnForkLazyLock<String> = WipeOnForkLazyLock::with_policies(
+///     || "127.0.0.1:5432".to_string(),
+///     ForkPolicy::Wipe,
+///     PoisonPolicy::Retry,
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PoisonPolicy {
+    /// Poison it, like `std::sync::Once`. Later calls to `call_once` and accesses to a
+    /// `WipeOnForkLazyLock` panic, and only `call_once_force` runs an initializer again. This is
+    /// the default.
+    ///
+    /// The poison is not inherited: a child process starts over as if no initializer had run,
+    /// since whatever made the parent's initializer panic may not apply to it.
+    #[default]
+    Poison,
+    /// Poison it like `Poison`, and keep it poisoned in the child processes too.
+    PoisonInherited,
+    /// Leave it uninitialized, so that the next call runs an initializer again, as if the
+    /// panicking one had never started. An initializer that calls
+    /// `poison()` on the state passed by `call_once_force` is treated the same way.
+    Retry,
+}
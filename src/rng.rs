@@ -0,0 +1,77 @@
+use crate::WipeOnForkMutex;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::sync::PoisonError;
+
+/// A random number generator that is seeded from the operating system in each process.
+///
+/// A child process that inherits an RNG from its parent produces the same stream of numbers as
+/// the parent, which breaks nonces, session IDs, and keys. Here, the RNG is seeded again with
+/// `SeedableRng::from_os_rng` on the first use in each process.
+///
+/// It is shared by all the threads and locked on each use. `rand::Rng` is implemented for
+/// `&WipeOnForkRng`:
+///
+/// ```
+/// use rand::Rng;
+/// use wipe_on_fork::WipeOnForkRng;
+///
+/// static RNG: WipeOnForkRng = WipeOnForkRng::new();
+///
+/// let nonce: [u8; 12] = (&RNG).random();
+/// let die = RNG.with(|rng| rng.random_range(1..=6));
+/// assert!((1..=6).contains(&die));
+/// ```
+pub struct WipeOnForkRng<R = StdRng> {
+    rng: WipeOnForkMutex<R>,
+}
+
+impl<R: SeedableRng> WipeOnForkRng<R> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> WipeOnForkRng<R> {
+        WipeOnForkRng {
+            rng: WipeOnForkMutex::new(R::from_os_rng),
+        }
+    }
+
+    /// Runs `f` with exclusive access to the RNG of the current process.
+    #[inline]
+    pub fn with<F, U>(&self, f: F) -> U
+    where
+        F: FnOnce(&mut R) -> U,
+    {
+        let mut rng = self.rng.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&mut rng)
+    }
+}
+
+impl<R: SeedableRng + RngCore> RngCore for &WipeOnForkRng<R> {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.with(|rng| rng.next_u32())
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.with(|rng| rng.next_u64())
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        self.with(|rng| rng.fill_bytes(dst))
+    }
+}
+
+impl<R: SeedableRng> Default for WipeOnForkRng<R> {
+    #[inline]
+    fn default() -> WipeOnForkRng<R> {
+        WipeOnForkRng::new()
+    }
+}
+
+impl<R> core::fmt::Debug for WipeOnForkRng<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WipeOnForkRng").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{ForkPolicy, WipeOnForkLazyLock};
+use std::future::Future;
+use tokio::runtime::{Builder, Handle, Runtime};
+use tokio::task::JoinHandle;
+
+fn multi_thread() -> Runtime {
+    Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build the tokio runtime")
+}
+
+fn current_thread() -> Runtime {
+    Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build the tokio runtime")
+}
+
+/// A tokio `Runtime` that is built lazily in each process.
+///
+/// A runtime created by the parent is unusable in a child process, since its worker threads do
+/// not exist there. This builds a new runtime on the first use in each process instead, and
+/// leaks the inherited one rather than dropping it, which would wait for the threads that are
+/// gone.
+///
+/// ```
+/// use wipe_on_fork::WipeOnForkRuntime;
+///
+/// static RUNTIME: WipeOnForkRuntime = WipeOnForkRuntime::multi_thread();
+///
+/// let answer = RUNTIME.block_on(async {
+///     tokio::spawn(async { 92 }).await.unwrap()
+/// });
+/// assert_eq!(answer, 92);
+/// ```
+pub struct WipeOnForkRuntime<F = fn() -> Runtime> {
+    runtime: WipeOnForkLazyLock<Runtime, F>,
+}
+
+impl WipeOnForkRuntime {
+    /// Creates a holder of a multi-threaded runtime with all the drivers enabled.
+    #[inline]
+    #[must_use]
+    pub const fn multi_thread() -> WipeOnForkRuntime {
+        WipeOnForkRuntime::new(multi_thread)
+    }
+
+    /// Creates a holder of a current-thread runtime with all the drivers enabled.
+    #[inline]
+    #[must_use]
+    pub const fn current_thread() -> WipeOnForkRuntime {
+        WipeOnForkRuntime::new(current_thread)
+    }
+}
+
+impl<F: FnMut() -> Runtime> WipeOnForkRuntime<F> {
+    /// Creates a holder of the runtime built by `f`.
+    ///
+    /// ```
+    /// use tokio::runtime::Builder;
+    /// use wipe_on_fork::WipeOnForkRuntime;
+    ///
+    /// static RUNTIME: WipeOnForkRuntime = WipeOnForkRuntime::new(|| {
+    ///     Builder::new_multi_thread()
+    ///         .worker_threads(2)
+    ///         .enable_all()
+    ///         .build()
+    ///         .unwrap()
+    /// });
+    ///
+    /// assert_eq!(RUNTIME.block_on(async { 92 }), 92);
+    /// ```
+    #[inline]
+    pub const fn new(f: F) -> WipeOnForkRuntime<F> {
+        WipeOnForkRuntime {
+            runtime: WipeOnForkLazyLock::with_policy(f, ForkPolicy::Forget),
+        }
+    }
+
+    /// Returns the runtime of the current process, building it if needed.
+    #[inline]
+    pub fn runtime(&self) -> &Runtime {
+        &self.runtime
+    }
+
+    #[inline]
+    pub fn handle(&self) -> &Handle {
+        self.runtime().handle()
+    }
+
+    #[inline]
+    pub fn block_on<Fut: Future>(&self, future: Fut) -> Fut::Output {
+        self.runtime().block_on(future)
+    }
+
+    #[inline]
+    pub fn spawn<Fut>(&self, future: Fut) -> JoinHandle<Fut::Output>
+    where
+        Fut: Future + Send + 'static,
+        Fut::Output: Send + 'static,
+    {
+        self.runtime().spawn(future)
+    }
+}
+
+impl Default for WipeOnForkRuntime {
+    #[inline]
+    fn default() -> WipeOnForkRuntime {
+        WipeOnForkRuntime::multi_thread()
+    }
+}
+
+impl<F> core::fmt::Debug for WipeOnForkRuntime<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WipeOnForkRuntime").finish_non_exhaustive()
+    }
+}
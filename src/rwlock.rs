@@ -0,0 +1,114 @@
+use crate::{ForkPolicy, WipeOnForkOnceLock};
+use std::sync::{LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockResult};
+
+/// An `RwLock` that is reset in the child process, together with the data that it protects.
+///
+/// Like [`WipeOnForkMutex`](crate::WipeOnForkMutex), the lock is created from `init` on the first
+/// use in each process, so a lock held by another thread of the parent at the time of the fork
+/// does not stay locked forever in the child. The data inherited from the parent is leaked
+/// rather than dropped, and the inherited lock stays where it is, so that a guard held across the
+/// fork does not alias the child's new lock.
+///
+/// ```
+/// use wipe_on_fork::WipeOnForkRwLock;
+///
+/// static CONFIG: WipeOnForkRwLock<String> = WipeOnForkRwLock::new(String::new);
+///
+/// CONFIG.write().unwrap().push_str("verbose");
+/// assert_eq!(*CONFIG.read().unwrap(), "verbose");
+/// ```
+pub struct WipeOnForkRwLock<T, F = fn() -> T> {
+    inner: WipeOnForkOnceLock<Box<RwLock<T>>>,
+    init: F,
+}
+
+impl<T, F: Fn() -> T> WipeOnForkRwLock<T, F> {
+    #[inline]
+    pub const fn new(init: F) -> WipeOnForkRwLock<T, F> {
+        WipeOnForkRwLock {
+            inner: WipeOnForkOnceLock::with_policy(ForkPolicy::Forget),
+            init,
+        }
+    }
+
+    #[inline]
+    fn lock(&self) -> &RwLock<T> {
+        self.inner
+            .get_or_init(|| Box::new(RwLock::new((self.init)())))
+    }
+
+    #[inline]
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
+        self.lock().read()
+    }
+
+    /// ```
+    /// use wipe_on_fork::WipeOnForkRwLock;
+    ///
+    /// let lock = WipeOnForkRwLock::new(|| 1u32);
+    ///
+    /// let reader = lock.read().unwrap();
+    /// assert_eq!(*lock.try_read().unwrap(), 1);
+    /// assert!(lock.try_write().is_err());
+    /// drop(reader);
+    /// ```
+    #[inline]
+    pub fn try_read(&self) -> TryLockResult<RwLockReadGuard<'_, T>> {
+        self.lock().try_read()
+    }
+
+    #[inline]
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+        self.lock().write()
+    }
+
+    #[inline]
+    pub fn try_write(&self) -> TryLockResult<RwLockWriteGuard<'_, T>> {
+        self.lock().try_write()
+    }
+
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.inner.get().is_some_and(|lock| lock.is_poisoned())
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        self.lock();
+        self.inner.get_mut().unwrap().get_mut()
+    }
+
+    #[inline]
+    pub fn into_inner(mut self) -> LockResult<T> {
+        match self.inner.take() {
+            Some(lock) => (*lock).into_inner(),
+            None => Ok((self.init)()),
+        }
+    }
+}
+
+impl<T, F> Drop for WipeOnForkRwLock<T, F> {
+    fn drop(&mut self) {
+        // The inherited data is leaked even if the lock is dropped before its first use in the
+        // child.
+        self.inner.discard_inherited();
+    }
+}
+
+impl<T: Default> Default for WipeOnForkRwLock<T> {
+    #[inline]
+    fn default() -> WipeOnForkRwLock<T> {
+        WipeOnForkRwLock::new(T::default)
+    }
+}
+
+impl<T: core::fmt::Debug, F> core::fmt::Debug for WipeOnForkRwLock<T, F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_tuple("WipeOnForkRwLock");
+        match self.inner.get() {
+            Some(v) => d.field(v),
+            None => d.field(&format_args!("<uninit>")),
+        };
+        d.finish()
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{ForkPolicy, WipeOnForkOnceCell, WipeOnForkOnceLock};
+use zeroize::Zeroize;
+
+fn zeroize_and_drop<T: Zeroize>(slot: &mut Option<T>) {
+    if let Some(value) = slot.as_mut() {
+        value.zeroize();
+    }
+    *slot = None;
+}
+
+fn zeroize_and_forget<T: Zeroize>(slot: &mut Option<T>) {
+    if let Some(value) = slot.as_mut() {
+        value.zeroize();
+    }
+    core::mem::forget(slot.take());
+}
+
+/// A [`WipeOnForkOnceCell`] that zeroizes the value inherited from the parent in place before
+/// discarding it, so that the child does not keep the parent's secret in its memory.
+///
+/// The value is also zeroized when the cell is dropped.
+///
+/// ```
+/// use wipe_on_fork::WipeOnForkSecretCell;
+///
+/// let key: WipeOnForkSecretCell<[u8; 32]> = WipeOnForkSecretCell::new();
+/// assert_eq!(key.get_or_init(|| [7u8; 32]), &[7u8; 32]);
+/// ```
+pub struct WipeOnForkSecretCell<T: Zeroize> {
+    inner: WipeOnForkOnceCell<T>,
+}
+
+impl<T: Zeroize> WipeOnForkSecretCell<T> {
+    /// Creates a new cell that drops the inherited value after zeroizing it.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        WipeOnForkSecretCell {
+            inner: WipeOnForkOnceCell::with_policy(ForkPolicy::Custom(zeroize_and_drop::<T>)),
+        }
+    }
+
+    /// Creates a new cell that leaks the inherited value after zeroizing it, without running its
+    /// `Drop` in the child.
+    #[inline]
+    #[must_use]
+    pub const fn new_forget() -> Self {
+        WipeOnForkSecretCell {
+            inner: WipeOnForkOnceCell::with_policy(ForkPolicy::Custom(zeroize_and_forget::<T>)),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.inner.get()
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.inner.get_mut()
+    }
+
+    #[inline]
+    pub fn set(&self, value: T) -> Result<(), T> {
+        self.inner.set(value)
+    }
+
+    #[inline]
+    pub fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        self.inner.get_or_init(f)
+    }
+
+    #[inline]
+    pub fn get_or_try_init<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        self.inner.get_or_try_init(f)
+    }
+}
+
+impl<T: Zeroize> Drop for WipeOnForkSecretCell<T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.inner.get_mut() {
+            value.zeroize();
+        }
+    }
+}
+
+impl<T: Zeroize> Default for WipeOnForkSecretCell<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Zeroize> core::fmt::Debug for WipeOnForkSecretCell<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WipeOnForkSecretCell")
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`WipeOnForkOnceLock`] that zeroizes the value inherited from the parent in place before
+/// discarding it, so that the child does not keep the parent's secret in its memory.
+///
+/// The value is also zeroized when the lock is dropped.
+///
+/// ```
+/// use wipe_on_fork::WipeOnForkSecretLock;
+///
+/// static SESSION_KEY: WipeOnForkSecretLock<[u8; 32]> = WipeOnForkSecretLock::new();
+///
+/// assert_eq!(SESSION_KEY.get_or_init(|| [7u8; 32]), &[7u8; 32]);
+/// ```
+pub struct WipeOnForkSecretLock<T: Zeroize> {
+    inner: WipeOnForkOnceLock<T>,
+}
+
+impl<T: Zeroize> WipeOnForkSecretLock<T> {
+    /// Creates a new lock that drops the inherited value after zeroizing it.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        WipeOnForkSecretLock {
+            inner: WipeOnForkOnceLock::with_policy(ForkPolicy::Custom(zeroize_and_drop::<T>)),
+        }
+    }
+
+    /// Creates a new lock that leaks the inherited value after zeroizing it, without running its
+    /// `Drop` in the child.
+    #[inline]
+    #[must_use]
+    pub const fn new_forget() -> Self {
+        WipeOnForkSecretLock {
+            inner: WipeOnForkOnceLock::with_policy(ForkPolicy::Custom(zeroize_and_forget::<T>)),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.inner.get()
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.inner.get_mut()
+    }
+
+    #[inline]
+    pub fn set(&self, value: T) -> Result<(), T> {
+        self.inner.set(value)
+    }
+
+    #[inline]
+    pub fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        self.inner.get_or_init(f)
+    }
+
+    #[inline]
+    pub fn get_or_try_init<F, E>(&self, f: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        self.inner.get_or_try_init(f)
+    }
+}
+
+impl<T: Zeroize> Drop for WipeOnForkSecretLock<T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.inner.get_mut() {
+            value.zeroize();
+        }
+    }
+}
+
+impl<T: Zeroize> Default for WipeOnForkSecretLock<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Zeroize> core::fmt::Debug for WipeOnForkSecretLock<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WipeOnForkSecretLock")
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,234 @@
+use crate::WipeOnForkAsyncOnceCell;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, SeqCst);
+    }
+}
+
+fn flag_waker() -> (Arc<Flag>, Waker) {
+    let flag = Arc::new(Flag(AtomicBool::new(false)));
+    (flag.clone(), Waker::from(flag))
+}
+
+struct ThreadWaker(std::thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = pin!(fut);
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+// Returns `Pending` once, and then `Ready`.
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+static A: WipeOnForkAsyncOnceCell<u32> = WipeOnForkAsyncOnceCell::new();
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork() {
+    assert_eq!(block_on(A.get_or_init(|| async { 1u32 })), &1u32);
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if A.get().is_some() {
+            expected_flag = 1u8;
+        }
+
+        if block_on(A.get_or_init(|| async { 2u32 })) != &2u32 {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(A.get(), Some(&1u32));
+}
+
+#[test]
+#[cfg(unix)]
+fn fork_while_initializing() {
+    static B: WipeOnForkAsyncOnceCell<u32> = WipeOnForkAsyncOnceCell::new();
+
+    // the parent is in the middle of running an initializer at the time of the fork
+    let (_flag, waker) = flag_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut pending = Box::pin(B.get_or_init(|| async {
+        YieldOnce(false).await;
+        1u32
+    }));
+    assert!(pending.as_mut().poll(&mut cx).is_pending());
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        let mut init = Box::pin(B.get_or_init(|| async { 2u32 }));
+        match init.as_mut().poll(&mut cx) {
+            Poll::Ready(&2u32) => {}
+            _ => expected_flag = 1u8,
+        }
+        drop(init);
+
+        // the parent's initializer does not release the gate of the child, and the child drops
+        // its own copy of the future, which the parent keeps
+        drop(unsafe { std::ptr::read(&pending) });
+        if B.get() != Some(&2u32) {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(block_on(pending), &1u32);
+}
+
+#[test]
+fn one_initializer_at_a_time() {
+    let cell: WipeOnForkAsyncOnceCell<u32> = WipeOnForkAsyncOnceCell::new();
+
+    let (_, first_waker) = flag_waker();
+    let (second_woken, second_waker) = flag_waker();
+
+    let mut first = Box::pin(cell.get_or_init(|| async {
+        YieldOnce(false).await;
+        1u32
+    }));
+    let mut second = Box::pin(cell.get_or_init(|| async { 2u32 }));
+
+    assert!(first
+        .as_mut()
+        .poll(&mut Context::from_waker(&first_waker))
+        .is_pending());
+    assert!(second
+        .as_mut()
+        .poll(&mut Context::from_waker(&second_waker))
+        .is_pending());
+    assert!(!second_woken.0.load(SeqCst));
+
+    assert_eq!(
+        first.as_mut().poll(&mut Context::from_waker(&first_waker)),
+        Poll::Ready(&1u32)
+    );
+    drop(first);
+    assert!(second_woken.0.load(SeqCst));
+    assert_eq!(
+        second
+            .as_mut()
+            .poll(&mut Context::from_waker(&second_waker)),
+        Poll::Ready(&1u32)
+    );
+}
+
+#[test]
+fn cancelled_initializer() {
+    let cell: WipeOnForkAsyncOnceCell<u32> = WipeOnForkAsyncOnceCell::new();
+
+    let (_, first_waker) = flag_waker();
+    let (second_woken, second_waker) = flag_waker();
+
+    let mut first = Box::pin(cell.get_or_init(|| async {
+        YieldOnce(false).await;
+        1u32
+    }));
+    let mut second = Box::pin(cell.get_or_init(|| async { 2u32 }));
+
+    assert!(first
+        .as_mut()
+        .poll(&mut Context::from_waker(&first_waker))
+        .is_pending());
+    assert!(second
+        .as_mut()
+        .poll(&mut Context::from_waker(&second_waker))
+        .is_pending());
+
+    // dropping the first future lets the second one run its initializer
+    drop(first);
+    assert!(second_woken.0.load(SeqCst));
+    assert_eq!(
+        second
+            .as_mut()
+            .poll(&mut Context::from_waker(&second_waker)),
+        Poll::Ready(&2u32)
+    );
+}
+
+#[test]
+fn failed_initializer() {
+    let cell: WipeOnForkAsyncOnceCell<u32> = WipeOnForkAsyncOnceCell::new();
+    assert_eq!(
+        block_on(cell.get_or_try_init(|| async { Err(()) })),
+        Err(())
+    );
+    assert_eq!(cell.get(), None);
+    assert_eq!(
+        block_on(cell.get_or_try_init(|| async { Ok::<u32, ()>(92) })),
+        Ok(&92)
+    );
+    assert_eq!(cell.into_inner(), Some(92));
+}
+
+#[test]
+fn threads() {
+    static C: WipeOnForkAsyncOnceCell<usize> = WipeOnForkAsyncOnceCell::new();
+    static CALLED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            std::thread::spawn(|| {
+                *block_on(C.get_or_init(|| async {
+                    YieldOnce(false).await;
+                    CALLED.fetch_add(1, SeqCst)
+                }))
+            })
+        })
+        .collect();
+    for t in threads {
+        assert_eq!(t.join().unwrap(), 0);
+    }
+    assert_eq!(CALLED.load(SeqCst), 1);
+}
+
+#[test]
+fn is_sync_send() {
+    fn assert_traits<T: Send + Sync>() {}
+    fn assert_send<T: Send>(_: &T) {}
+    assert_traits::<WipeOnForkAsyncOnceCell<String>>();
+
+    let cell: WipeOnForkAsyncOnceCell<String> = WipeOnForkAsyncOnceCell::new();
+    assert_send(&cell.get_or_init(|| async { String::new() }));
+}
@@ -0,0 +1,68 @@
+use crate::WipeOnForkLazyLock;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use wipe_on_fork_macros::wipe_on_fork;
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+struct Counted(u32);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, SeqCst);
+    }
+}
+
+/// A documented item.
+#[wipe_on_fork]
+pub(crate) static WIPE: u32 = std::process::id();
+
+#[wipe_on_fork(policy = "keep")]
+static KEEP: u32 = std::process::id();
+
+#[wipe_on_fork(policy = "reinit")]
+static REINIT: u32 = std::process::id();
+
+#[wipe_on_fork(policy = "wipe", on_wipe = "forget")]
+static FORGET: Counted = Counted(std::process::id());
+
+#[wipe_on_fork(policy = "reinit", on_wipe = "forget")]
+static REINIT_FORGET: Counted = Counted(std::process::id());
+
+#[test]
+fn expands_to_lazy_locks() {
+    let _: &WipeOnForkLazyLock<u32> = &WIPE;
+    let _: &WipeOnForkLazyLock<Counted> = &FORGET;
+    assert_eq!(*WIPE, std::process::id());
+    assert_eq!(*KEEP, std::process::id());
+}
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork() {
+    assert_eq!(*WIPE, std::process::id());
+    assert_eq!(*KEEP, std::process::id());
+    assert_eq!(*REINIT, std::process::id());
+    assert_eq!(FORGET.0, std::process::id());
+    assert_eq!(REINIT_FORGET.0, std::process::id());
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+        let pid = std::process::id();
+        let parent = unsafe { libc::getppid() } as u32;
+
+        if *WIPE != pid || *KEEP != parent || *REINIT != pid {
+            expected_flag = 1u8;
+        }
+
+        if FORGET.0 != pid || REINIT_FORGET.0 != pid {
+            expected_flag = 1u8;
+        }
+
+        if DROPPED.load(SeqCst) != 0 {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+}
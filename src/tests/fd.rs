@@ -0,0 +1,86 @@
+use crate::{set_cloexec, FdPolicy, WipeOnForkFd};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+static CLOSE: WipeOnForkFd = WipeOnForkFd::new();
+static KEEP: WipeOnForkFd = WipeOnForkFd::with_policy(FdPolicy::Keep);
+static LEAK: WipeOnForkFd = WipeOnForkFd::with_policy(FdPolicy::Leak);
+
+fn open_pipe() -> (OwnedFd, OwnedFd) {
+    let mut fds: [libc::c_int; 2] = [0; 2];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) }
+}
+
+fn is_open(fd: libc::c_int) -> bool {
+    unsafe { libc::fcntl(fd, libc::F_GETFD) != -1 }
+}
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork() {
+    let (_close_reader, close_writer) = open_pipe();
+    let (_keep_reader, keep_writer) = open_pipe();
+    let (_leak_reader, leak_writer) = open_pipe();
+    let close_fd = CLOSE.get_or_init(|| close_writer).as_raw_fd();
+    let keep_fd = KEEP.get_or_init(|| keep_writer).as_raw_fd();
+    let leak_fd = LEAK.get_or_init(|| leak_writer).as_raw_fd();
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if CLOSE.get().is_some() || is_open(close_fd) {
+            expected_flag = 1u8;
+        }
+
+        if KEEP.get().map(|fd| fd.as_raw_fd()) != Some(keep_fd) || !is_open(keep_fd) {
+            expected_flag = 1u8;
+        }
+
+        if LEAK.get().is_some() || !is_open(leak_fd) {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    // the child's `close()` does not affect the parent's copy
+    assert!(is_open(close_fd));
+    assert_eq!(CLOSE.get().map(|fd| fd.as_raw_fd()), Some(close_fd));
+}
+
+#[test]
+#[cfg(unix)]
+fn drop_in_child_leaks() {
+    let (_reader, writer) = open_pipe();
+    let fd = writer.as_raw_fd();
+    let leak = WipeOnForkFd::with_policy(FdPolicy::Leak);
+    leak.set(writer).unwrap();
+
+    assert!(super::run_in_child(move || {
+        drop(leak);
+        is_open(fd)
+    }));
+}
+
+#[test]
+fn cloexec() {
+    let (reader, _writer) = open_pipe();
+    let flags = unsafe { libc::fcntl(reader.as_raw_fd(), libc::F_GETFD) };
+    assert_eq!(flags & libc::FD_CLOEXEC, 0);
+
+    set_cloexec(&reader).unwrap();
+    let flags = unsafe { libc::fcntl(reader.as_raw_fd(), libc::F_GETFD) };
+    assert_ne!(flags & libc::FD_CLOEXEC, 0);
+}
+
+#[test]
+fn take_and_into_inner() {
+    let (reader, writer) = open_pipe();
+    let raw = reader.as_raw_fd();
+
+    let mut cell = WipeOnForkFd::default();
+    assert!(cell.set(reader).is_ok());
+    assert!(cell.set(writer).is_err());
+    assert_eq!(cell.take().map(|fd| fd.as_raw_fd()), Some(raw));
+    assert!(cell.into_inner().is_none());
+}
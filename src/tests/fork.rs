@@ -0,0 +1,61 @@
+use crate::fork::{self, ForkToken};
+use std::cell::Cell;
+
+thread_local! {
+    static LOG: Cell<u64> = const { Cell::new(0) };
+    static PARENT_GENERATION: Cell<u64> = const { Cell::new(u64::MAX) };
+}
+
+fn log(digit: u64) {
+    LOG.with(|log| log.set(log.get().wrapping_mul(10).wrapping_add(digit)));
+}
+
+#[test]
+#[cfg(unix)]
+fn token_is_stale_in_child() {
+    let token = ForkToken::new();
+    assert_eq!(token.generation(), fork::generation());
+    assert!(!token.is_stale());
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if !token.is_stale() {
+            expected_flag = 1u8;
+        }
+
+        if fork::generation() != token.generation() + 1 {
+            expected_flag = 1u8;
+        }
+
+        if ForkToken::new().is_stale() {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert!(!token.is_stale());
+}
+
+#[test]
+#[cfg(unix)]
+fn callbacks_run_in_order() {
+    // The callbacks are global, but they only log into the thread that forks, so forks made by
+    // other tests do not interfere.
+    fork::on_prepare(|| log(1));
+    fork::on_prepare(|| log(2));
+    fork::on_parent(|| log(3));
+    fork::on_parent(|| log(4));
+    fork::on_child(|| {
+        let expected = PARENT_GENERATION.with(|gen| gen.get()).wrapping_add(1);
+        log(if fork::generation() == expected { 5 } else { 9 })
+    });
+    fork::on_child(|| log(6));
+
+    PARENT_GENERATION.with(|gen| gen.set(fork::generation()));
+    LOG.with(|log| log.set(0));
+
+    assert!(super::run_in_child(|| LOG.with(|log| log.get()) == 2156));
+    assert_eq!(LOG.with(|log| log.get()), 2134);
+}
@@ -4,9 +4,9 @@ use std::ops::Deref;
 #[test]
 #[cfg(unix)]
 fn wipe_on_fork() {
-    let a = WipeOnForkLazyCell::new(|| std::process::id());
+    let a = WipeOnForkLazyCell::new(std::process::id);
 
-    let cur_process_id: u32 = a.deref().clone();
+    let cur_process_id: u32 = *a.deref();
 
     let mut pipefd: [libc::c_int; 2] = [libc::c_int::default(), libc::c_int::default()];
 
@@ -57,3 +57,38 @@ fn wipe_on_fork() {
         assert_eq!(expected_flag, 0u8);
     }
 }
+
+#[test]
+#[cfg(unix)]
+fn forget_on_fork() {
+    use crate::ForkPolicy;
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    static DROP_CNT: AtomicUsize = AtomicUsize::new(0);
+    struct Dropper(u32);
+    impl Drop for Dropper {
+        fn drop(&mut self) {
+            DROP_CNT.fetch_add(1, SeqCst);
+        }
+    }
+
+    let a = WipeOnForkLazyCell::with_policy(|| Dropper(std::process::id()), ForkPolicy::Forget);
+
+    assert_eq!(a.0, std::process::id());
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if a.0 != std::process::id() {
+            expected_flag = 1u8;
+        }
+
+        if DROP_CNT.load(SeqCst) != 0 {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(DROP_CNT.load(SeqCst), 0);
+}
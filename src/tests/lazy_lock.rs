@@ -1,10 +1,10 @@
-use crate::{WipeOnForkLazyCell, WipeOnForkLazyLock, WipeOnForkOnceLock};
+use crate::{ForkPolicy, WipeOnForkLazyCell, WipeOnForkLazyLock, WipeOnForkOnceLock};
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering::SeqCst;
 use std::sync::Mutex;
 use std::thread;
 
-static A: WipeOnForkLazyLock<u32> = WipeOnForkLazyLock::new(|| std::process::id());
+static A: WipeOnForkLazyLock<u32> = WipeOnForkLazyLock::new(std::process::id);
 
 #[test]
 #[cfg(unix)]
@@ -57,6 +57,125 @@ fn wipe_on_fork() {
     }
 }
 
+static KEEP: WipeOnForkLazyLock<u32> =
+    WipeOnForkLazyLock::with_policy(std::process::id, ForkPolicy::Keep);
+static TRANSFORM: WipeOnForkLazyLock<u32> =
+    WipeOnForkLazyLock::with_policy(|| 1u32, ForkPolicy::Transform(|v| v + 1));
+
+#[test]
+#[cfg(unix)]
+fn fork_policies() {
+    let parent_process_id = *KEEP;
+    assert_eq!(*TRANSFORM, 1u32);
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if *KEEP != parent_process_id {
+            expected_flag = 1u8;
+        }
+
+        if *TRANSFORM != 2u32 {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+}
+
+#[test]
+#[cfg(unix)]
+fn drop_in_child_does_not_recompute() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn count() -> u32 {
+        CALLS.fetch_add(1, SeqCst);
+        1
+    }
+
+    let reinit = WipeOnForkLazyLock::with_policy(count, ForkPolicy::Reinit(count));
+    let transform = WipeOnForkLazyLock::with_policy(
+        count,
+        ForkPolicy::Transform(|v| {
+            CALLS.fetch_add(1, SeqCst);
+            v + 1
+        }),
+    );
+    assert_eq!(*reinit, 1);
+    assert_eq!(*transform, 1);
+    assert_eq!(CALLS.load(SeqCst), 2);
+
+    // dropping the cells in the child only discards the inherited values
+    assert!(super::run_in_child(move || {
+        drop(reinit);
+        drop(transform);
+        CALLS.load(SeqCst) == 2
+    }));
+}
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork_capturing_closure() {
+    static VALUE_DROPS: AtomicUsize = AtomicUsize::new(0);
+    static FUNC_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Value(String);
+    impl Drop for Value {
+        fn drop(&mut self) {
+            VALUE_DROPS.fetch_add(1, SeqCst);
+        }
+    }
+
+    struct Captured(String);
+    impl Drop for Captured {
+        fn drop(&mut self) {
+            FUNC_DROPS.fetch_add(1, SeqCst);
+        }
+    }
+
+    let captured = Captured("pid".to_string());
+    let a =
+        WipeOnForkLazyLock::new(move || Value(format!("{}-{}", captured.0, std::process::id())));
+
+    assert_eq!(a.0, format!("pid-{}", std::process::id()));
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if a.0 != format!("pid-{}", std::process::id()) {
+            expected_flag = 1u8;
+        }
+
+        if VALUE_DROPS.load(SeqCst) != 1 || FUNC_DROPS.load(SeqCst) != 0 {
+            expected_flag = 1u8;
+        }
+
+        // the child drops its own copy, which the parent keeps
+        drop(unsafe { std::ptr::read(&a) });
+
+        if VALUE_DROPS.load(SeqCst) != 2 || FUNC_DROPS.load(SeqCst) != 1 {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(VALUE_DROPS.load(SeqCst), 0);
+
+    let value = WipeOnForkLazyLock::into_inner(a).ok().unwrap();
+    assert_eq!(value.0, format!("pid-{}", std::process::id()));
+    assert_eq!(FUNC_DROPS.load(SeqCst), 1);
+    drop(value);
+    assert_eq!(VALUE_DROPS.load(SeqCst), 1);
+}
+
+#[test]
+fn into_inner_returns_closure() {
+    let hello = "Hello, World!".to_string();
+    let lazy = WipeOnForkLazyLock::new(move || hello.to_uppercase());
+    let mut f = WipeOnForkLazyLock::into_inner(lazy).err().unwrap();
+    assert_eq!(f(), "HELLO, WORLD!");
+}
+
 fn spawn_and_wait<R: Send + 'static>(f: impl FnOnce() -> R + Send + 'static) -> R {
     thread::spawn(f).join().unwrap()
 }
@@ -144,13 +263,7 @@ fn sync_lazy_default() {
 
 #[test]
 fn static_sync_lazy() {
-    static XS: WipeOnForkLazyLock<Vec<i32>> = WipeOnForkLazyLock::new(|| {
-        let mut xs = Vec::new();
-        xs.push(1);
-        xs.push(2);
-        xs.push(3);
-        xs
-    });
+    static XS: WipeOnForkLazyLock<Vec<i32>> = WipeOnForkLazyLock::new(|| vec![1, 2, 3]);
 
     spawn_and_wait(|| {
         assert_eq!(&*XS, &vec![1, 2, 3]);
@@ -163,13 +276,7 @@ fn static_sync_lazy() {
 fn static_sync_lazy_via_fn() {
     fn xs() -> &'static Vec<i32> {
         static XS: WipeOnForkOnceLock<Vec<i32>> = WipeOnForkOnceLock::new();
-        XS.get_or_init(|| {
-            let mut xs = Vec::new();
-            xs.push(1);
-            xs.push(2);
-            xs.push(3);
-            xs
-        })
+        XS.get_or_init(|| vec![1, 2, 3])
     }
     assert_eq!(xs(), &vec![1, 2, 3]);
 }
@@ -181,6 +288,33 @@ fn sync_lazy_poisoning() {
         let res = std::panic::catch_unwind(|| x.len());
         assert!(res.is_err());
     }
+    assert!(WipeOnForkLazyLock::is_poisoned(&x));
+}
+
+#[test]
+fn sync_lazy_retry() {
+    use crate::PoisonPolicy;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static X: WipeOnForkLazyLock<String> = WipeOnForkLazyLock::with_policies(
+        || {
+            if CALLS.fetch_add(1, SeqCst) < 2 {
+                panic!("not yet");
+            }
+            "ready".to_string()
+        },
+        ForkPolicy::Wipe,
+        PoisonPolicy::Retry,
+    );
+
+    for _ in 0..2 {
+        let res = std::panic::catch_unwind(|| X.len());
+        assert!(res.is_err());
+        assert!(!WipeOnForkLazyLock::is_poisoned(&X));
+    }
+    assert_eq!(&*X, "ready");
+    assert_eq!(&*X, "ready");
+    assert_eq!(CALLS.load(SeqCst), 3);
 }
 
 // Check that we can infer `T` from closure's type.
@@ -194,3 +328,60 @@ fn is_sync_send() {
     fn assert_traits<T: Send + Sync>() {}
     assert_traits::<WipeOnForkLazyLock<String>>();
 }
+
+#[test]
+fn racing_first_access() {
+    static CALLED: AtomicUsize = AtomicUsize::new(0);
+    static LAZY: WipeOnForkLazyLock<usize> = WipeOnForkLazyLock::new(|| {
+        thread::sleep(std::time::Duration::from_millis(50));
+        CALLED.fetch_add(1, SeqCst) + 92
+    });
+
+    let barrier = std::sync::Arc::new(std::sync::Barrier::new(8));
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                *LAZY
+            })
+        })
+        .collect();
+
+    for t in threads {
+        assert_eq!(t.join().unwrap(), 92);
+    }
+    assert_eq!(CALLED.load(SeqCst), 1);
+}
+
+#[test]
+#[cfg(unix)]
+fn poison_cleared_in_child() {
+    static PARENT: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(true);
+    static X: WipeOnForkLazyLock<u32> = WipeOnForkLazyLock::new(|| {
+        assert!(!PARENT.load(SeqCst), "kaboom");
+        92
+    });
+
+    let res = std::panic::catch_unwind(|| *X);
+    assert!(res.is_err());
+    assert!(WipeOnForkLazyLock::is_poisoned(&X));
+
+    assert!(super::run_in_child(|| {
+        PARENT.store(false, SeqCst);
+
+        let mut expected_flag = 0u8;
+
+        if WipeOnForkLazyLock::is_poisoned(&X) {
+            expected_flag = 1u8;
+        }
+
+        if std::panic::catch_unwind(|| *X).ok() != Some(92) {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert!(WipeOnForkLazyLock::is_poisoned(&X));
+}
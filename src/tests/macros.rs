@@ -0,0 +1,59 @@
+use crate::wipe_on_fork_lazy_static;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+
+static CALLED: AtomicUsize = AtomicUsize::new(0);
+
+wipe_on_fork_lazy_static! {
+    /// A documented item.
+    #[allow(dead_code)]
+    static ref A: u32 = {
+        CALLED.fetch_add(1, SeqCst);
+        1
+    };
+    pub(crate) static ref B: Vec<&'static str> = vec!["Spica", "Hoyten"];
+    pub static ref C: String = format!("{}-{}", *A, B.len());
+}
+
+mod nested {
+    use crate::wipe_on_fork_lazy_static;
+
+    wipe_on_fork_lazy_static! {
+        pub(super) static ref D: u32 = super::C.len() as u32;
+    }
+}
+
+#[test]
+fn expands_to_lazy_locks() {
+    let _: &crate::WipeOnForkLazyLock<u32> = &A;
+    let _: &crate::WipeOnForkLazyLock<Vec<&'static str>> = &B;
+
+    assert_eq!(*A, 1);
+    assert_eq!(*A, 1);
+    assert_eq!(CALLED.load(SeqCst), 1);
+    assert_eq!(B.len(), 2);
+    assert_eq!(C.as_str(), "1-2");
+    assert_eq!(*nested::D, 3);
+}
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork() {
+    wipe_on_fork_lazy_static! {
+        static ref PID: u32 = std::process::id();
+    }
+
+    assert_eq!(*PID, std::process::id());
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if *PID != std::process::id() {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(*PID, std::process::id());
+}
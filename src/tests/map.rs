@@ -0,0 +1,69 @@
+use crate::WipeOnForkMap;
+use std::sync::Arc;
+
+static A: WipeOnForkMap<&'static str, Arc<u32>> = WipeOnForkMap::new();
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork() {
+    assert_eq!(
+        *A.get_or_insert_with("pid", || Arc::new(std::process::id())),
+        std::process::id()
+    );
+    A.insert("one", Arc::new(1));
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if !A.is_empty() || A.get("one").is_some() {
+            expected_flag = 1u8;
+        }
+
+        if *A.get_or_insert_with("pid", || Arc::new(std::process::id())) != std::process::id() {
+            expected_flag = 1u8;
+        }
+
+        if A.len() != 1 {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(A.len(), 2);
+    assert_eq!(A.get("pid").as_deref(), Some(&std::process::id()));
+}
+
+#[test]
+fn insert_remove_clear() {
+    let map: WipeOnForkMap<String, u32> = WipeOnForkMap::default();
+    assert!(map.is_empty());
+    assert_eq!(map.insert("a".to_string(), 1), None);
+    assert_eq!(map.insert("a".to_string(), 2), Some(1));
+    assert!(map.contains_key("a"));
+    assert_eq!(map.get("a"), Some(2));
+    assert_eq!(map.remove("a"), Some(2));
+    assert_eq!(map.remove("a"), None);
+
+    map.insert("b".to_string(), 3);
+    map.with(|m| m.insert("c".to_string(), 4));
+    assert_eq!(map.len(), 2);
+    map.clear();
+    assert!(map.is_empty());
+}
+
+#[test]
+fn reentrant_get_or_insert_with() {
+    let map: WipeOnForkMap<u32, u32> = WipeOnForkMap::new();
+    let value = map.get_or_insert_with(1, || {
+        assert_eq!(map.get_or_insert_with(1, || 10), 10);
+        20
+    });
+    assert_eq!(value, 10);
+}
+
+#[test]
+fn is_sync_send() {
+    fn assert_traits<T: Send + Sync>() {}
+    assert_traits::<WipeOnForkMap<String, Arc<u32>>>();
+}
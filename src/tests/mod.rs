@@ -1,6 +1,98 @@
+#[cfg(feature = "std")]
+mod async_once_cell;
+mod attribute;
+#[cfg(all(unix, feature = "std"))]
+mod fd;
+mod fork;
 mod lazy_cell;
 mod lazy_lock;
+mod macros;
+#[cfg(feature = "std")]
+mod map;
+#[cfg(feature = "std")]
+mod mutex;
 mod once;
 mod once_cell;
 mod once_lock;
+#[cfg(all(target_os = "linux", feature = "std"))]
+mod page;
+#[cfg(feature = "rand")]
+mod rng;
+#[cfg(feature = "tokio")]
+mod runtime;
+#[cfg(feature = "std")]
+mod rwlock;
+#[cfg(feature = "zeroize")]
+mod secret;
+#[cfg(feature = "std")]
+mod thread_local;
+#[cfg(feature = "rayon")]
+mod thread_pool;
 mod utils;
+
+/// Forks, runs `f` in the child, and returns what it returned, or `false` if it panicked. Panics
+/// if the child does not report back within ten seconds, which is taken as a deadlock.
+#[cfg(unix)]
+fn run_in_child<F>(f: F) -> bool
+where
+    F: FnOnce() -> bool,
+{
+    let mut pipefd: [libc::c_int; 2] = [libc::c_int::default(), libc::c_int::default()];
+
+    unsafe { libc::pipe(pipefd.as_mut_ptr()) };
+
+    let res = unsafe { libc::fork() };
+
+    if res == 0 {
+        // child
+        unsafe {
+            libc::close(pipefd[0]);
+        }
+
+        let ok = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or(false);
+        let expected_flag = if ok { 0u8 } else { 1u8 };
+
+        unsafe {
+            libc::write(
+                pipefd[1],
+                &expected_flag as *const u8 as *const libc::c_void,
+                1,
+            );
+            libc::close(pipefd[1]);
+            libc::_exit(0);
+        }
+    }
+
+    // parent
+    assert!(res > 0);
+    unsafe {
+        libc::close(pipefd[1]);
+    }
+
+    let mut poll_fd = libc::pollfd {
+        fd: pipefd[0],
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    if unsafe { libc::poll(&mut poll_fd, 1, 10_000) } == 0 {
+        unsafe {
+            libc::kill(res, libc::SIGKILL);
+            libc::waitpid(res, std::ptr::null_mut(), 0);
+            libc::close(pipefd[0]);
+        }
+        panic!("the child process appears to be deadlocked");
+    }
+
+    let mut expected_flag = 2u8;
+    unsafe {
+        libc::read(
+            pipefd[0],
+            (&mut expected_flag) as *mut u8 as *mut libc::c_void,
+            1,
+        );
+        libc::close(pipefd[0]);
+        libc::waitpid(res, std::ptr::null_mut(), 0);
+    }
+
+    expected_flag == 0u8
+}
@@ -0,0 +1,123 @@
+use crate::WipeOnForkMutex;
+use std::sync::mpsc::channel;
+use std::thread;
+
+static A: WipeOnForkMutex<u32> = WipeOnForkMutex::new(|| 0u32);
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork() {
+    *A.lock().unwrap() = 5;
+
+    // another thread holds the lock while the process forks
+    let (locked_tx, locked_rx) = channel();
+    let (release_tx, release_rx) = channel::<()>();
+    let holder = thread::spawn(move || {
+        let _guard = A.lock().unwrap();
+        locked_tx.send(()).unwrap();
+        release_rx.recv().unwrap();
+    });
+    locked_rx.recv().unwrap();
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        match A.try_lock() {
+            Ok(mut guard) => {
+                if *guard != 0u32 {
+                    expected_flag = 1u8;
+                }
+                *guard = 7;
+            }
+            Err(_) => expected_flag = 1u8,
+        }
+
+        if *A.lock().unwrap() != 7u32 {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert!(A.try_lock().is_err());
+
+    release_tx.send(()).unwrap();
+    holder.join().unwrap();
+    assert_eq!(*A.lock().unwrap(), 5u32);
+}
+
+#[test]
+#[cfg(unix)]
+fn guard_held_across_fork() {
+    let mutex = WipeOnForkMutex::new(|| 0u32);
+    let mut guard = mutex.lock().unwrap();
+    *guard = 5;
+
+    // the forking thread's guard still refers to the parent's mutex, not to the child's
+    assert!(super::run_in_child(|| {
+        let new_guard = mutex.lock().unwrap();
+        !std::ptr::eq(&*new_guard, &*guard) && *new_guard == 0 && *guard == 5
+    }));
+
+    drop(guard);
+    assert_eq!(*mutex.lock().unwrap(), 5);
+}
+
+#[test]
+#[cfg(unix)]
+fn drop_in_child_leaks() {
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    static DROP_CNT: AtomicUsize = AtomicUsize::new(0);
+    struct Dropper;
+    impl Drop for Dropper {
+        fn drop(&mut self) {
+            DROP_CNT.fetch_add(1, SeqCst);
+        }
+    }
+
+    let mutex = WipeOnForkMutex::new(|| Dropper);
+    drop(mutex.lock().unwrap());
+
+    assert!(super::run_in_child(move || {
+        drop(mutex);
+        DROP_CNT.load(SeqCst) == 0
+    }));
+
+    assert_eq!(DROP_CNT.load(SeqCst), 1);
+}
+
+#[test]
+fn default_and_into_inner() {
+    let mutex: WipeOnForkMutex<Vec<u32>> = WipeOnForkMutex::default();
+    mutex.lock().unwrap().push(1);
+    assert_eq!(mutex.into_inner().unwrap(), vec![1]);
+
+    let mutex = WipeOnForkMutex::new(|| 3u32);
+    assert_eq!(mutex.into_inner().unwrap(), 3);
+}
+
+#[test]
+fn poisoning() {
+    let mutex = std::sync::Arc::new(WipeOnForkMutex::new(|| 0u32));
+    let cloned = mutex.clone();
+    let _ = thread::spawn(move || {
+        let _guard = cloned.lock().unwrap();
+        panic!();
+    })
+    .join();
+    assert!(mutex.is_poisoned());
+    assert!(mutex.lock().is_err());
+}
+
+#[test]
+fn is_poisoned_does_not_initialize() {
+    let mutex = WipeOnForkMutex::new(|| -> u32 { panic!("initialized") });
+    assert!(!mutex.is_poisoned());
+}
+
+#[test]
+fn is_sync_send() {
+    fn assert_traits<T: Send + Sync>() {}
+    assert_traits::<WipeOnForkMutex<String>>();
+}
@@ -10,8 +10,8 @@ fn wipe_on_fork() {
     A.call_once(|| {});
     B.call_once(|| {});
 
-    assert_eq!(A.is_completed(), true);
-    assert_eq!(B.is_completed(), true);
+    assert!(A.is_completed());
+    assert!(B.is_completed());
 
     let mut pipefd: [libc::c_int; 2] = [libc::c_int::default(), libc::c_int::default()];
 
@@ -141,6 +141,7 @@ fn poison_bad() {
         O.call_once(|| {});
     });
     assert!(t.is_err());
+    assert!(O.is_poisoned());
 
     // we can subvert poisoning, however
     let mut called = false;
@@ -152,6 +153,34 @@ fn poison_bad() {
 
     // once any success happens, we stop propagating the poison
     O.call_once(|| {});
+    assert!(!O.is_poisoned());
+}
+
+#[test]
+fn retry_after_panic() {
+    use crate::PoisonPolicy;
+
+    static O: WipeOnForkOnce = WipeOnForkOnce::with_poison_policy(PoisonPolicy::Retry);
+
+    let t = std::panic::catch_unwind(|| {
+        O.call_once(|| panic!());
+    });
+    assert!(t.is_err());
+    assert!(!O.is_poisoned());
+    assert!(!O.is_completed());
+
+    // an explicit poison is not kept either
+    O.call_once_force(|p| {
+        assert!(!p.is_poisoned());
+        p.poison();
+    });
+    assert!(!O.is_poisoned());
+    assert!(!O.is_completed());
+
+    let mut called = false;
+    O.call_once(|| called = true);
+    assert!(called);
+    assert!(O.is_completed());
 }
 
 #[test]
@@ -191,3 +220,103 @@ fn wait_for_force_to_finish() {
     assert!(t1.join().is_ok());
     assert!(t2.join().is_ok());
 }
+
+#[test]
+fn recursive_call_panics() {
+    static O: WipeOnForkOnce = WipeOnForkOnce::new();
+
+    let t = std::panic::catch_unwind(|| {
+        O.call_once(|| {
+            O.call_once(|| {});
+        });
+    });
+    assert!(t.is_err());
+
+    // the outer call is poisoned by the panic, but another thread is not mistaken for recursion
+    std::thread::spawn(|| {
+        O.call_once_force(|p| assert!(p.is_poisoned()));
+    })
+    .join()
+    .unwrap();
+    assert!(O.is_completed());
+}
+
+#[test]
+#[cfg(unix)]
+fn poison_across_fork() {
+    use crate::PoisonPolicy;
+
+    static CLEARED: WipeOnForkOnce = WipeOnForkOnce::new();
+    static INHERITED: WipeOnForkOnce =
+        WipeOnForkOnce::with_poison_policy(PoisonPolicy::PoisonInherited);
+
+    for once in [&CLEARED, &INHERITED] {
+        let t = std::panic::catch_unwind(|| {
+            once.call_once(|| panic!());
+        });
+        assert!(t.is_err());
+        assert!(once.is_poisoned());
+    }
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if CLEARED.is_poisoned() || !INHERITED.is_poisoned() {
+            expected_flag = 1u8;
+        }
+
+        let mut called = false;
+        CLEARED.call_once(|| called = true);
+        if !called || !CLEARED.is_completed() {
+            expected_flag = 1u8;
+        }
+
+        let t = std::panic::catch_unwind(|| {
+            INHERITED.call_once(|| {});
+        });
+        if t.is_ok() {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    // the parent is still poisoned either way
+    assert!(CLEARED.is_poisoned());
+    assert!(INHERITED.is_poisoned());
+}
+
+#[test]
+#[cfg(unix)]
+fn fork_during_initializer() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static O: WipeOnForkOnce = WipeOnForkOnce::new();
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    static RELEASE: AtomicBool = AtomicBool::new(false);
+
+    let initializer = std::thread::spawn(|| {
+        O.call_once(|| {
+            STARTED.store(true, Ordering::Release);
+            while !RELEASE.load(Ordering::Acquire) {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+        });
+    });
+    while !STARTED.load(Ordering::Acquire) {
+        std::thread::yield_now();
+    }
+
+    // the initializing thread is gone in the child, so the `Once` must not wait for it
+    let child_ok = super::run_in_child(|| {
+        let mut called = false;
+        O.call_once(|| called = true);
+        called && O.is_completed()
+    });
+
+    assert!(!O.is_completed());
+    RELEASE.store(true, Ordering::Release);
+    initializer.join().unwrap();
+    assert!(O.is_completed());
+    assert!(child_ok);
+}
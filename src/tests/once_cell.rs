@@ -40,21 +40,21 @@ fn wipe_on_fork() {
 
         let mut expected_flag = 0u8;
 
-        if !a.get().is_none() {
+        if a.get().is_some() {
             expected_flag = 1u8;
         }
 
-        if !b.get().is_some() {
+        if b.get().is_none() {
             expected_flag = 1u8;
         }
 
         let _ = a.get_or_init(|| 2u32);
         let _ = b.get_or_init(|| 2u32);
 
-        if !a.get().unwrap() == 2 {
+        if *a.get().unwrap() != 2 {
             expected_flag = 1u8;
         }
-        if !b.get().unwrap() == 1 {
+        if *b.get().unwrap() != 1 {
             expected_flag = 1u8;
         }
 
@@ -88,3 +88,66 @@ fn wipe_on_fork() {
         assert_eq!(expected_flag, 0u8);
     }
 }
+
+#[test]
+#[cfg(unix)]
+fn forget_on_fork() {
+    use crate::ForkPolicy;
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    static DROP_CNT: AtomicUsize = AtomicUsize::new(0);
+    struct Dropper;
+    impl Drop for Dropper {
+        fn drop(&mut self) {
+            DROP_CNT.fetch_add(1, SeqCst);
+        }
+    }
+
+    let forget = WipeOnForkOnceCell::with_policy(ForkPolicy::Forget);
+    let wipe = WipeOnForkOnceCell::with_policy(ForkPolicy::Wipe);
+
+    let _ = forget.get_or_init(|| Dropper);
+    let _ = wipe.get_or_init(|| Dropper);
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if forget.get().is_some() {
+            expected_flag = 1u8;
+        }
+
+        if DROP_CNT.load(SeqCst) != 0 {
+            expected_flag = 1u8;
+        }
+
+        if wipe.get().is_some() {
+            expected_flag = 1u8;
+        }
+
+        if DROP_CNT.load(SeqCst) != 1 {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert!(forget.get().is_some());
+    assert!(wipe.get().is_some());
+    assert_eq!(DROP_CNT.load(SeqCst), 0);
+}
+
+#[test]
+#[cfg(unix)]
+fn take_and_clone_keep_policy() {
+    use crate::ForkPolicy;
+
+    let mut cell = WipeOnForkOnceCell::with_policy(ForkPolicy::Keep);
+    cell.set(1u32).unwrap();
+    assert_eq!(cell.take(), Some(1));
+    cell.set(2u32).unwrap();
+    let clone = cell.clone();
+
+    assert!(super::run_in_child(|| {
+        cell.get() == Some(&2u32) && clone.get() == Some(&2u32)
+    }));
+}
@@ -1,4 +1,4 @@
-use crate::WipeOnForkOnceLock;
+use crate::{ForkPolicy, WipeOnForkOnceLock};
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering::SeqCst;
 use std::sync::OnceLock;
@@ -13,8 +13,8 @@ fn wipe_on_fork() {
     A.get_or_init(|| 1u32);
     B.get_or_init(|| 1u32);
 
-    assert_eq!(A.get().is_some(), true);
-    assert_eq!(B.get().is_some(), true);
+    assert!(A.get().is_some());
+    assert!(B.get().is_some());
 
     let mut pipefd: [libc::c_int; 2] = [libc::c_int::default(), libc::c_int::default()];
 
@@ -34,7 +34,7 @@ fn wipe_on_fork() {
             expected_flag = 1u8;
         }
 
-        if !B.get().is_some() {
+        if B.get().is_none() {
             expected_flag = 1u8;
         }
 
@@ -161,6 +161,7 @@ fn get_or_try_init() {
     assert!(cell.get().is_none());
 
     assert_eq!(cell.get_or_try_init(|| Err(())), Err(()));
+    assert!(!cell.is_poisoned());
 
     assert_eq!(
         cell.get_or_try_init(|| Ok::<_, ()>("hello".to_string())),
@@ -169,6 +170,84 @@ fn get_or_try_init() {
     assert_eq!(cell.get(), Some(&"hello".to_string()));
 }
 
+#[test]
+fn take_keeps_poison_policy() {
+    use crate::PoisonPolicy;
+
+    let mut cell = WipeOnForkOnceLock::with_policies(ForkPolicy::Wipe, PoisonPolicy::Retry);
+    cell.set(1u32).unwrap();
+    assert_eq!(cell.take(), Some(1));
+
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        cell.get_or_init(|| panic!());
+    }));
+    assert!(res.is_err());
+    assert!(!cell.is_poisoned());
+    assert_eq!(cell.get_or_init(|| 2), &2);
+}
+
+#[test]
+#[cfg(unix)]
+fn clone_keeps_policies() {
+    use crate::PoisonPolicy;
+
+    let cell = WipeOnForkOnceLock::with_policies(ForkPolicy::Keep, PoisonPolicy::Retry);
+    cell.set(1u32).unwrap();
+    let clone = cell.clone();
+
+    assert!(super::run_in_child(|| clone.get() == Some(&1u32)));
+
+    let clone =
+        WipeOnForkOnceLock::<u32>::with_policies(ForkPolicy::Wipe, PoisonPolicy::Retry).clone();
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        clone.get_or_init(|| panic!());
+    }));
+    assert!(res.is_err());
+    assert!(!clone.is_poisoned());
+}
+
+#[test]
+fn failed_try_init_does_not_poison() {
+    use std::sync::atomic::AtomicBool;
+
+    static CELL: WipeOnForkOnceLock<usize> = WipeOnForkOnceLock::new();
+    static FAILURES: AtomicUsize = AtomicUsize::new(0);
+    static SAW_POISON: AtomicBool = AtomicBool::new(false);
+
+    let threads: Vec<_> = (0..8)
+        .map(|i| {
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    if CELL.is_poisoned() {
+                        SAW_POISON.store(true, SeqCst);
+                    }
+                }
+                // the first few initializers fail, and the others must still get to run
+                let res = CELL.get_or_try_init(|| {
+                    if FAILURES.fetch_add(1, SeqCst) < 4 {
+                        Err(i)
+                    } else {
+                        Ok(i)
+                    }
+                });
+                if CELL.is_poisoned() {
+                    SAW_POISON.store(true, SeqCst);
+                }
+                res.is_ok()
+            })
+        })
+        .collect();
+
+    let successes = threads
+        .into_iter()
+        .map(|t| t.join().unwrap())
+        .filter(|ok| *ok)
+        .count();
+    assert_eq!(successes, 4);
+    assert!(CELL.get().is_some());
+    assert!(!SAW_POISON.load(SeqCst));
+}
+
 #[test]
 fn from_impl() {
     assert_eq!(WipeOnForkOnceLock::from("value").get(), Some(&"value"));
@@ -265,3 +344,85 @@ fn dropck() {
         cell.set(&s).unwrap();
     }
 }
+
+static KEEP: WipeOnForkOnceLock<u32> = WipeOnForkOnceLock::with_policy(ForkPolicy::Keep);
+static REINIT: WipeOnForkOnceLock<u32> =
+    WipeOnForkOnceLock::with_policy(ForkPolicy::Reinit(std::process::id));
+static TRANSFORM: WipeOnForkOnceLock<u32> =
+    WipeOnForkOnceLock::with_policy(ForkPolicy::Transform(|v| v + 1));
+static CUSTOM: WipeOnForkOnceLock<u32> =
+    WipeOnForkOnceLock::with_policy(ForkPolicy::Custom(|slot| {
+        if *slot == Some(1u32) {
+            *slot = None;
+        }
+    }));
+
+#[test]
+#[cfg(unix)]
+fn fork_policies() {
+    KEEP.get_or_init(|| 1u32);
+    REINIT.get_or_init(std::process::id);
+    TRANSFORM.get_or_init(|| 1u32);
+    CUSTOM.get_or_init(|| 1u32);
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if KEEP.get() != Some(&1u32) {
+            expected_flag = 1u8;
+        }
+
+        if REINIT.get() != Some(&std::process::id()) {
+            expected_flag = 1u8;
+        }
+
+        if TRANSFORM.get() != Some(&2u32) {
+            expected_flag = 1u8;
+        }
+
+        if CUSTOM.get().is_some() {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(REINIT.get(), Some(&std::process::id()));
+    assert_eq!(TRANSFORM.get(), Some(&1u32));
+}
+
+static SLOW_TRANSFORM: WipeOnForkOnceLock<u32> =
+    WipeOnForkOnceLock::with_policy(ForkPolicy::Transform(|v| {
+        thread::sleep(std::time::Duration::from_millis(100));
+        v + 1
+    }));
+
+#[test]
+#[cfg(unix)]
+fn readers_wait_for_fork_policy() {
+    SLOW_TRANSFORM.get_or_init(|| 1u32);
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        // While one thread transforms the inherited value, the others must wait for it rather
+        // than find the lock uninitialized.
+        let readers: Vec<_> = (0..4)
+            .map(|_| thread::spawn(|| SLOW_TRANSFORM.get().copied()))
+            .collect();
+
+        if SLOW_TRANSFORM.get() != Some(&2u32) {
+            expected_flag = 1u8;
+        }
+
+        for reader in readers {
+            if reader.join().ok().flatten() != Some(2u32) {
+                expected_flag = 1u8;
+            }
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(SLOW_TRANSFORM.get(), Some(&1u32));
+}
@@ -0,0 +1,171 @@
+use crate::WipeOnForkPage;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use std::thread;
+
+static A: WipeOnForkPage<[u8; 32]> = WipeOnForkPage::new();
+static B: WipeOnForkPage<[u8; 32]> = WipeOnForkPage::new();
+
+#[test]
+fn wipe_on_fork() {
+    A.get_or_init(|| [1u8; 32]);
+    assert_eq!(A.get(), Some(&[1u8; 32]));
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if A.get().is_some() {
+            expected_flag = 1u8;
+        }
+
+        if *A.get_or_init(|| [2u8; 32]) != [2u8; 32] {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(A.get(), Some(&[1u8; 32]));
+}
+
+#[test]
+fn wipe_on_raw_fork() {
+    B.get_or_init(|| [1u8; 32]);
+
+    let mut pipefd: [libc::c_int; 2] = [libc::c_int::default(), libc::c_int::default()];
+
+    unsafe { libc::pipe(pipefd.as_mut_ptr()) };
+
+    // No `pthread_atfork` handler runs for a raw `clone`, so this relies on the kernel alone.
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_clone,
+            libc::SIGCHLD as libc::c_long,
+            0 as libc::c_long,
+            0 as libc::c_long,
+            0 as libc::c_long,
+            0 as libc::c_long,
+        )
+    };
+
+    if res == 0 {
+        // child
+        unsafe {
+            libc::close(pipefd[0]);
+        }
+
+        let mut expected_flag = 0u8;
+
+        if B.get().is_some() {
+            expected_flag = 1u8;
+        }
+
+        unsafe {
+            libc::write(
+                pipefd[1],
+                &expected_flag as *const u8 as *const libc::c_void,
+                1,
+            );
+            libc::close(pipefd[1]);
+            libc::_exit(0);
+        }
+    } else {
+        // parent
+        unsafe {
+            libc::close(pipefd[1]);
+        }
+
+        let mut expected_flag = 2u8;
+        unsafe {
+            libc::read(
+                pipefd[0],
+                (&mut expected_flag) as *mut u8 as *mut libc::c_void,
+                1,
+            );
+            libc::waitpid(res as libc::pid_t, std::ptr::null_mut(), 0);
+        }
+
+        assert_eq!(expected_flag, 0u8);
+        assert_eq!(B.get(), Some(&[1u8; 32]));
+    }
+}
+
+#[test]
+fn page_drop() {
+    static DROP_CNT: AtomicUsize = AtomicUsize::new(0);
+    struct Dropper;
+    impl Drop for Dropper {
+        fn drop(&mut self) {
+            DROP_CNT.fetch_add(1, SeqCst);
+        }
+    }
+
+    let x = WipeOnForkPage::new();
+    thread::spawn(move || {
+        x.get_or_init(|| Dropper);
+        assert_eq!(DROP_CNT.load(SeqCst), 0);
+        drop(x);
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(DROP_CNT.load(SeqCst), 1);
+}
+
+#[test]
+fn page_poisoning() {
+    let x: WipeOnForkPage<u64> = WipeOnForkPage::new();
+    let res = std::panic::catch_unwind(|| x.get_or_init(|| panic!("kaboom")));
+    assert!(res.is_err());
+    assert!(x.get().is_none());
+    assert_eq!(x.get_or_init(|| 92), &92);
+}
+
+#[test]
+fn page_stampede() {
+    static P: WipeOnForkPage<u64> = WipeOnForkPage::new();
+    static CALLED: AtomicUsize = AtomicUsize::new(0);
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            thread::spawn(|| {
+                *P.get_or_init(|| {
+                    CALLED.fetch_add(1, SeqCst);
+                    92
+                })
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 92);
+    }
+    assert_eq!(CALLED.load(SeqCst), 1);
+}
+
+#[test]
+fn waiter_retries_after_panic() {
+    let x: WipeOnForkPage<u64> = WipeOnForkPage::new();
+    let (started_tx, started_rx) = std::sync::mpsc::channel();
+
+    thread::scope(|s| {
+        let initializer = s.spawn(|| {
+            x.get_or_init(|| {
+                started_tx.send(()).unwrap();
+                thread::sleep(std::time::Duration::from_millis(50));
+                panic!("kaboom")
+            });
+        });
+
+        // this thread blocks until the initializer panics, then initializes the cell itself
+        started_rx.recv().unwrap();
+        assert_eq!(x.get_or_init(|| 92), &92);
+        assert!(initializer.join().is_err());
+    });
+}
+
+#[test]
+fn is_sync_send() {
+    fn assert_traits<T: Send + Sync>() {}
+    assert_traits::<WipeOnForkPage<[u8; 32]>>();
+}
@@ -0,0 +1,50 @@
+use crate::WipeOnForkRng;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::sync::Mutex;
+
+static A: WipeOnForkRng = WipeOnForkRng::new();
+static B: Mutex<Option<StdRng>> = Mutex::new(None);
+
+fn next_values() -> [u64; 2] {
+    let a = (&A).next_u64();
+    let b = B.lock().unwrap().as_mut().unwrap().next_u64();
+    [a, b]
+}
+
+#[test]
+#[cfg(unix)]
+fn reseed_on_fork() {
+    *B.lock().unwrap() = Some(StdRng::from_os_rng());
+    next_values();
+
+    // the values that the parent draws next
+    let expected = [
+        A.with(|rng| rng.clone().next_u64()),
+        B.lock().unwrap().clone().unwrap().next_u64(),
+    ];
+
+    // the inherited `StdRng` repeats the parent's stream, the wipe-on-fork one does not
+    assert!(super::run_in_child(|| {
+        let values = next_values();
+        values[1] == expected[1] && values[0] != expected[0]
+    }));
+
+    assert_eq!(next_values(), expected);
+}
+
+#[test]
+fn fill_bytes() {
+    let rng: WipeOnForkRng<StdRng> = WipeOnForkRng::default();
+    let mut a = [0u8; 32];
+    let mut b = [0u8; 32];
+    (&rng).fill_bytes(&mut a);
+    rng.with(|rng| rng.fill_bytes(&mut b));
+    assert_ne!(a, b);
+}
+
+#[test]
+fn is_sync_send() {
+    fn assert_traits<T: Send + Sync>() {}
+    assert_traits::<WipeOnForkRng>();
+}
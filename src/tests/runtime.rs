@@ -0,0 +1,39 @@
+use crate::WipeOnForkRuntime;
+
+static A: WipeOnForkRuntime = WipeOnForkRuntime::multi_thread();
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork() {
+    assert_eq!(
+        A.block_on(async { tokio::spawn(async { 1u32 }).await.unwrap() }),
+        1u32
+    );
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        // the workers of the parent's runtime do not exist here, so this would hang if the
+        // inherited runtime were used
+        let spawned = A.spawn(async { 2u32 });
+        if A.block_on(spawned).ok() != Some(2u32) {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(A.handle().block_on(async { 3u32 }), 3u32);
+}
+
+#[test]
+fn current_thread() {
+    let runtime = WipeOnForkRuntime::current_thread();
+    assert_eq!(runtime.block_on(async { 92 }), 92);
+}
+
+#[test]
+fn is_sync_send() {
+    fn assert_traits<T: Send + Sync>() {}
+    assert_traits::<WipeOnForkRuntime>();
+}
@@ -0,0 +1,88 @@
+use crate::WipeOnForkRwLock;
+use std::sync::mpsc::channel;
+use std::thread;
+
+static A: WipeOnForkRwLock<u32> = WipeOnForkRwLock::new(|| 0u32);
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork() {
+    *A.write().unwrap() = 5;
+
+    // another thread holds the write lock while the process forks
+    let (locked_tx, locked_rx) = channel();
+    let (release_tx, release_rx) = channel::<()>();
+    let holder = thread::spawn(move || {
+        let _guard = A.write().unwrap();
+        locked_tx.send(()).unwrap();
+        release_rx.recv().unwrap();
+    });
+    locked_rx.recv().unwrap();
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        match A.try_read() {
+            Ok(guard) => {
+                if *guard != 0u32 {
+                    expected_flag = 1u8;
+                }
+            }
+            Err(_) => expected_flag = 1u8,
+        }
+
+        match A.try_write() {
+            Ok(mut guard) => *guard = 7,
+            Err(_) => expected_flag = 1u8,
+        }
+
+        if *A.read().unwrap() != 7u32 {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert!(A.try_read().is_err());
+
+    release_tx.send(()).unwrap();
+    holder.join().unwrap();
+    assert_eq!(*A.read().unwrap(), 5u32);
+}
+
+#[test]
+#[cfg(unix)]
+fn guard_held_across_fork() {
+    let lock = WipeOnForkRwLock::new(|| 0u32);
+    let mut guard = lock.write().unwrap();
+    *guard = 5;
+
+    // the forking thread's guard still refers to the parent's lock, not to the child's
+    assert!(super::run_in_child(|| {
+        let new_guard = lock.write().unwrap();
+        !std::ptr::eq(&*new_guard, &*guard) && *new_guard == 0 && *guard == 5
+    }));
+
+    drop(guard);
+    assert_eq!(*lock.read().unwrap(), 5);
+}
+
+#[test]
+fn default_and_get_mut() {
+    let mut lock: WipeOnForkRwLock<Vec<u32>> = WipeOnForkRwLock::default();
+    lock.get_mut().unwrap().push(1);
+    assert_eq!(*lock.read().unwrap(), vec![1]);
+    assert_eq!(lock.into_inner().unwrap(), vec![1]);
+}
+
+#[test]
+fn is_poisoned_does_not_initialize() {
+    let lock = WipeOnForkRwLock::new(|| -> u32 { panic!("initialized") });
+    assert!(!lock.is_poisoned());
+}
+
+#[test]
+fn is_sync_send() {
+    fn assert_traits<T: Send + Sync>() {}
+    assert_traits::<WipeOnForkRwLock<String>>();
+}
@@ -0,0 +1,71 @@
+use crate::{WipeOnForkSecretCell, WipeOnForkSecretLock};
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use zeroize::Zeroize;
+
+static ZEROED_DROPS: AtomicUsize = AtomicUsize::new(0);
+static DIRTY_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+struct Key([u8; 32]);
+
+impl Zeroize for Key {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        if self.0 == [0u8; 32] {
+            ZEROED_DROPS.fetch_add(1, SeqCst);
+        } else {
+            DIRTY_DROPS.fetch_add(1, SeqCst);
+        }
+    }
+}
+
+static A: WipeOnForkSecretLock<Key> = WipeOnForkSecretLock::new();
+static B: WipeOnForkSecretLock<[u8; 32]> = WipeOnForkSecretLock::new_forget();
+
+#[test]
+#[cfg(unix)]
+fn zeroize_on_fork() {
+    A.get_or_init(|| Key([7u8; 32]));
+    let b: *const [u8; 32] = B.get_or_init(|| [7u8; 32]);
+    let cell = WipeOnForkSecretCell::new();
+    cell.get_or_init(|| Key([7u8; 32]));
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        let zeroed_drops = ZEROED_DROPS.load(SeqCst);
+
+        if A.get().is_some() || cell.get().is_some() {
+            expected_flag = 1u8;
+        }
+
+        if ZEROED_DROPS.load(SeqCst) != zeroed_drops + 2 || DIRTY_DROPS.load(SeqCst) != 0 {
+            expected_flag = 1u8;
+        }
+
+        // The forgotten value stays where it was, but zeroized.
+        if B.get().is_some() || unsafe { *b } != [0u8; 32] {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(A.get().map(|key| key.0), Some([7u8; 32]));
+    assert_eq!(B.get(), Some(&[7u8; 32]));
+}
+
+#[test]
+fn zeroize_on_drop() {
+    let cell = WipeOnForkSecretCell::new();
+    cell.get_or_init(|| Key([7u8; 32]));
+
+    let zeroed_drops = ZEROED_DROPS.load(SeqCst);
+    drop(cell);
+    assert!(ZEROED_DROPS.load(SeqCst) > zeroed_drops);
+}
@@ -0,0 +1,132 @@
+use crate::WipeOnForkThreadLocal;
+use std::cell::Cell;
+use std::thread;
+
+static A: WipeOnForkThreadLocal<u32> = WipeOnForkThreadLocal::new();
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork() {
+    assert_eq!(A.get_or(|| 1u32), &1u32);
+    thread::spawn(|| {
+        assert_eq!(A.get_or(|| 2u32), &2u32);
+    })
+    .join()
+    .unwrap();
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if A.get().is_some() {
+            expected_flag = 1u8;
+        }
+
+        if A.get_or(|| 3u32) != &3u32 {
+            expected_flag = 1u8;
+        }
+
+        let other = thread::spawn(|| *A.get_or(|| 4u32)).join();
+        if other.ok() != Some(4u32) {
+            expected_flag = 1u8;
+        }
+
+        if A.get() != Some(&3u32) {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(A.get(), Some(&1u32));
+}
+
+#[test]
+#[cfg(unix)]
+fn reference_across_fork() {
+    let tls: WipeOnForkThreadLocal<String> = WipeOnForkThreadLocal::new();
+    let parent = tls.get_or(|| "parent".to_string());
+
+    // the child's value does not take the place of the inherited one
+    assert!(super::run_in_child(|| {
+        tls.get_or(|| "child".to_string()) == "child" && parent == "parent"
+    }));
+}
+
+#[test]
+fn per_thread() {
+    let tls: WipeOnForkThreadLocal<Cell<u32>> = WipeOnForkThreadLocal::new();
+    tls.get_or_default().set(1);
+
+    // IDs are recycled, so the threads take theirs before any of them may exit, or one could
+    // find the value of another.
+    let barrier = std::sync::Barrier::new(4);
+    thread::scope(|s| {
+        for i in 2..6 {
+            let tls = &tls;
+            let barrier = &barrier;
+            s.spawn(move || {
+                assert_eq!(tls.get(), None);
+                barrier.wait();
+                tls.get_or_default().set(i);
+                assert_eq!(tls.get_or_default().get(), i);
+            });
+        }
+    });
+
+    assert_eq!(tls.get_or_default().get(), 1);
+}
+
+#[test]
+fn iter_mut_and_clear() {
+    let mut tls: WipeOnForkThreadLocal<u32> = WipeOnForkThreadLocal::new();
+    tls.get_or(|| 1);
+    thread::scope(|s| {
+        s.spawn(|| tls.get_or(|| 2));
+    });
+
+    let mut values: Vec<u32> = tls.iter_mut().map(|v| *v).collect();
+    values.sort();
+    assert_eq!(values, vec![1, 2]);
+
+    tls.clear();
+    assert_eq!(tls.get(), None);
+    assert_eq!(tls.iter_mut().count(), 0);
+}
+
+#[test]
+fn reentrant_create() {
+    let tls: WipeOnForkThreadLocal<u32> = WipeOnForkThreadLocal::new();
+    let value = tls.get_or(|| {
+        assert_eq!(tls.get_or(|| 1), &1);
+        2
+    });
+    assert_eq!(value, &1);
+}
+
+#[test]
+fn get_or_try() {
+    let tls: WipeOnForkThreadLocal<u32> = WipeOnForkThreadLocal::new();
+    assert_eq!(tls.get_or_try(|| Err(())), Err(()));
+    assert_eq!(tls.get(), None);
+    assert_eq!(tls.get_or_try(|| Ok::<u32, ()>(1)), Ok(&1));
+}
+
+#[test]
+fn is_sync_send() {
+    fn assert_traits<T: Send + Sync>() {}
+    assert_traits::<WipeOnForkThreadLocal<Cell<u32>>>();
+}
+
+#[test]
+fn exited_threads_give_back_their_slots() {
+    let mut tls: WipeOnForkThreadLocal<u32> = WipeOnForkThreadLocal::new();
+    for _ in 0..256 {
+        thread::scope(|s| {
+            s.spawn(|| tls.get_or(|| 1));
+        });
+    }
+
+    // Each thread takes the smallest free ID, so the threads mostly reuse the same slot, although
+    // the threads of other tests may take some of the IDs in between.
+    assert!(tls.iter_mut().count() < 256);
+}
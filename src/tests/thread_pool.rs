@@ -0,0 +1,58 @@
+use crate::WipeOnForkThreadPool;
+use rayon::prelude::*;
+
+static A: WipeOnForkThreadPool = WipeOnForkThreadPool::with_defaults();
+
+#[test]
+#[cfg(unix)]
+fn wipe_on_fork() {
+    assert_eq!(
+        A.install(|| (1..=100u64).into_par_iter().sum::<u64>()),
+        5050
+    );
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        // the threads of the parent's pool do not exist here, so this would hang if the
+        // inherited pool were used
+        if A.install(|| (1..=100u64).into_par_iter().sum::<u64>()) != 5050 {
+            expected_flag = 1u8;
+        }
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        A.spawn(move || tx.send(2u32).unwrap());
+        if rx.recv().ok() != Some(2u32) {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(A.install(|| (1..=10u64).into_par_iter().sum::<u64>()), 55);
+}
+
+#[test]
+fn scope() {
+    let pool = WipeOnForkThreadPool::new(|| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap()
+    });
+    assert_eq!(pool.current_num_threads(), 2);
+
+    let mut a = 0;
+    let mut b = 0;
+    pool.scope(|s| {
+        s.spawn(|_| a = 1);
+        s.spawn(|_| b = 2);
+    });
+    assert_eq!((a, b), (1, 2));
+}
+
+#[test]
+fn is_sync_send() {
+    fn assert_traits<T: Send + Sync>() {}
+    assert_traits::<WipeOnForkThreadPool>();
+}
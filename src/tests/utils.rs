@@ -96,3 +96,216 @@ fn generation_test() {
         assert_eq!(expected_flag, 0u8);
     }
 }
+
+#[test]
+#[cfg(unix)]
+fn fork_while_reading_generation() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let base = GENERATION.get();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut readers = Vec::new();
+    for _ in 0..8 {
+        let stop = stop.clone();
+        readers.push(std::thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                assert_eq!(GENERATION.get(), base);
+            }
+        }));
+    }
+
+    for _ in 0..32 {
+        assert!(super::run_in_child(|| GENERATION.get() == base + 1));
+    }
+
+    stop.store(true, Ordering::Relaxed);
+    for reader in readers {
+        reader.join().unwrap();
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn pid_detection_catches_raw_fork() {
+    use crate::{set_fork_detection, ForkDetection, WipeOnForkOnceLock};
+
+    static A: WipeOnForkOnceLock<u32> = WipeOnForkOnceLock::new();
+
+    // SAFETY: the test process was created by `fork()`, so the recorded PID is current and the
+    // generation does not change.
+    unsafe { set_fork_detection(ForkDetection::Both) };
+
+    let base = GENERATION.get();
+    A.get_or_init(|| 1u32);
+
+    let mut pipefd: [libc::c_int; 2] = [libc::c_int::default(), libc::c_int::default()];
+
+    unsafe { libc::pipe(pipefd.as_mut_ptr()) };
+
+    // A raw `clone` without `CLONE_VM` creates a child process without running the
+    // `pthread_atfork` handlers.
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_clone,
+            libc::SIGCHLD as libc::c_long,
+            0 as libc::c_long,
+            0 as libc::c_long,
+            0 as libc::c_long,
+            0 as libc::c_long,
+        )
+    };
+
+    if res == 0 {
+        // child
+        unsafe {
+            libc::close(pipefd[0]);
+        }
+
+        let mut expected_flag = 0u8;
+
+        if GENERATION.get() != base + 1 {
+            expected_flag = 1u8;
+        }
+
+        if A.get().is_some() {
+            expected_flag = 1u8;
+        }
+
+        unsafe {
+            libc::write(
+                pipefd[1],
+                &expected_flag as *const u8 as *const libc::c_void,
+                1,
+            );
+            libc::close(pipefd[1]);
+            libc::_exit(0);
+        }
+    } else {
+        // parent
+        unsafe {
+            libc::close(pipefd[1]);
+        }
+
+        let mut expected_flag = 2u8;
+        unsafe {
+            libc::read(
+                pipefd[0],
+                (&mut expected_flag) as *mut u8 as *mut libc::c_void,
+                1,
+            );
+            libc::waitpid(res as libc::pid_t, std::ptr::null_mut(), 0);
+        }
+
+        // SAFETY: turning PID detection off never changes the generation.
+        unsafe { set_fork_detection(ForkDetection::AtFork) };
+
+        assert_eq!(GENERATION.get(), base);
+        assert_eq!(A.get(), Some(&1u32));
+        assert_eq!(expected_flag, 0u8);
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn custom_fork_detector() {
+    use crate::{set_fork_detector, AtForkDetector, ForkDetector, PidDetector, WipeOnForkOnceLock};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static RESPAWNS: AtomicU64 = AtomicU64::new(0);
+    static A: WipeOnForkOnceLock<u32> = WipeOnForkOnceLock::new();
+
+    struct Respawns;
+
+    // SAFETY: only bumped by the child below, which is single-threaded, while it holds no
+    // reference into a wipe-on-fork value.
+    unsafe impl ForkDetector for Respawns {
+        fn generation(&self) -> u64 {
+            RESPAWNS.load(Ordering::Acquire)
+        }
+    }
+
+    let at_fork = AtForkDetector.generation();
+    let pid = PidDetector.generation();
+
+    assert!(super::run_in_child(|| {
+        let mut expected_flag = 0u8;
+
+        if AtForkDetector.generation() != at_fork + 1 {
+            expected_flag = 1u8;
+        }
+
+        if PidDetector.generation() != pid + 1 {
+            expected_flag = 1u8;
+        }
+
+        // Only the child switches to the custom detector, which does not count the fork.
+        // SAFETY: the child is single-threaded, and only uses `A` from now on, which the parent
+        // has not initialized.
+        unsafe { set_fork_detector(&Respawns) };
+        if crate::fork::generation() != 0 {
+            expected_flag = 1u8;
+        }
+
+        A.get_or_init(|| 1u32);
+        RESPAWNS.fetch_add(1, Ordering::AcqRel);
+
+        if A.get().is_some() {
+            expected_flag = 1u8;
+        }
+
+        if crate::fork::generation() != 1 {
+            expected_flag = 1u8;
+        }
+
+        expected_flag == 0u8
+    }));
+
+    assert_eq!(AtForkDetector.generation(), at_fork);
+    assert_eq!(PidDetector.generation(), pid);
+}
+
+#[test]
+#[cfg(unix)]
+fn pid_detector_bumps_once() {
+    use crate::{ForkDetector, PidDetector};
+    use std::sync::{Arc, Barrier};
+
+    let base = PidDetector.generation();
+
+    assert!(super::run_in_child(|| {
+        // all the threads notice the new PID at the same time, but only one of them bumps
+        let barrier = Arc::new(Barrier::new(8));
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    PidDetector.generation()
+                })
+            })
+            .collect();
+        readers
+            .into_iter()
+            .all(|reader| reader.join().unwrap() == base + 1)
+            && PidDetector.generation() == base + 1
+    }));
+}
+
+#[test]
+#[cfg(unix)]
+fn take_over_registration_interrupted_by_fork() {
+    use crate::utils::{GenerationCounter, REGISTERED, REGISTERING};
+    use std::sync::atomic::Ordering;
+
+    // The child only registers its handlers in itself, and exits right after.
+    assert!(super::run_in_child(|| {
+        // as if the parent's thread had been registering, with PID 1, when the process forked
+        let counter = GenerationCounter::new();
+        counter
+            .registration
+            .store(REGISTERING | 1 << 32, Ordering::Release);
+        counter.get() == 0 && counter.registration.load(Ordering::Acquire) == REGISTERED
+    }));
+}
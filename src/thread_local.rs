@@ -0,0 +1,336 @@
+use crate::WipeOnForkMutex;
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::PoisonError;
+
+// Thread `id` is kept in bucket `log2(id) + 1`, which holds `2^(bucket - 1)` threads, except for
+// bucket 0, which only holds thread 0. The buckets are allocated on first use and never move, so
+// a thread finds its slot without taking a lock.
+const BUCKETS: usize = usize::BITS as usize + 1;
+
+#[inline]
+fn bucket_size(bucket: usize) -> usize {
+    1 << bucket.saturating_sub(1)
+}
+
+/// A value of a thread, tagged with the generation in which it was created.
+struct Slot<T> {
+    generation: u64,
+    value: T,
+}
+
+/// A per-thread value, like `ThreadLocal` in the `thread_local` crate, whose slots are all
+/// discarded in the child process.
+///
+/// After a fork, only the forking thread exists in the child. The values of the other threads
+/// would never be used again, and the forking thread's own value still holds the parent's
+/// state. Here, each thread gets a fresh slot in each process instead.
+///
+/// As in the `thread_local` crate, each thread has a small ID, which indexes the slots without a
+/// lock, and is recycled when the thread exits. The value of an exited thread is not dropped
+/// until the `WipeOnForkThreadLocal` is, so a new thread may find it. The values inherited from
+/// the parent are leaked rather than dropped in the child, as their threads may have been in the
+/// middle of using them at the time of the fork.
+///
+/// ```
+/// use std::cell::Cell;
+///
+/// use wipe_on_fork::WipeOnForkThreadLocal;
+///
+/// static COUNTER: WipeOnForkThreadLocal<Cell<u32>> = WipeOnForkThreadLocal::new();
+///
+/// COUNTER.get_or_default().set(1);
+/// std::thread::spawn(|| {
+///     assert_eq!(COUNTER.get_or_default().get(), 0);
+/// })
+/// .join()
+/// .unwrap();
+/// assert_eq!(COUNTER.get_or_default().get(), 1);
+/// ```
+pub struct WipeOnForkThreadLocal<T: Send> {
+    buckets: [AtomicPtr<AtomicPtr<Slot<T>>>; BUCKETS],
+}
+
+// Each thread only accesses its own slot through `&self`, or the slot of an exited thread, which
+// is why `T: Send` is needed but not `T: Sync`.
+unsafe impl<T: Send> Sync for WipeOnForkThreadLocal<T> {}
+
+unsafe impl<T: Send> Send for WipeOnForkThreadLocal<T> {}
+
+impl<T: Send> WipeOnForkThreadLocal<T> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> WipeOnForkThreadLocal<T> {
+        WipeOnForkThreadLocal {
+            buckets: [const { AtomicPtr::new(ptr::null_mut()) }; BUCKETS],
+        }
+    }
+
+    /// Returns the value of the current thread in the current process, if any.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        let generation = crate::utils::generation();
+        let slot = self
+            .entry(current_thread(generation))?
+            .load(Ordering::Acquire);
+        if slot.is_null() {
+            return None;
+        }
+        // A slot is only dropped through `&mut self`, so it stays where it is while `self` is
+        // borrowed.
+        let slot = unsafe { &*slot };
+        (slot.generation == generation).then_some(&slot.value)
+    }
+
+    /// Returns the value of the current thread in the current process, creating it with `create`
+    /// if there is none.
+    ///
+    /// `create` may access this `WipeOnForkThreadLocal` too. If it creates a value for the
+    /// current thread that way, that value is kept, and the one returned by `create` is dropped.
+    #[inline]
+    pub fn get_or<F>(&self, create: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        match self.get_or_try(|| Ok::<T, core::convert::Infallible>(create())) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    #[inline]
+    pub fn get_or_try<F, E>(&self, create: F) -> Result<&T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+    {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+
+        let value = create()?;
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+        Ok(self.insert(value))
+    }
+
+    #[cold]
+    fn insert(&self, value: T) -> &T {
+        let generation = crate::utils::generation();
+        let thread = current_thread(generation);
+        let entry = match self.entry(thread) {
+            Some(entry) => entry,
+            None => {
+                self.allocate_bucket(thread.bucket);
+                self.entry(thread).unwrap()
+            }
+        };
+
+        // Only the current thread writes to its entry. A slot from an earlier generation is
+        // replaced but leaked, since the forking thread may still hold a reference into it.
+        let slot = Box::into_raw(Box::new(Slot { generation, value }));
+        entry.store(slot, Ordering::Release);
+        unsafe { &(*slot).value }
+    }
+
+    #[inline]
+    fn entry(&self, thread: Thread) -> Option<&AtomicPtr<Slot<T>>> {
+        let bucket = self.buckets[thread.bucket].load(Ordering::Acquire);
+        if bucket.is_null() {
+            None
+        } else {
+            Some(unsafe { &*bucket.add(thread.index) })
+        }
+    }
+
+    fn allocate_bucket(&self, bucket: usize) {
+        let entries: Box<[AtomicPtr<Slot<T>>]> = (0..bucket_size(bucket))
+            .map(|_| AtomicPtr::new(ptr::null_mut()))
+            .collect();
+        let entries = Box::into_raw(entries) as *mut AtomicPtr<Slot<T>>;
+        match self.buckets[bucket].compare_exchange(
+            ptr::null_mut(),
+            entries,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => {}
+            // Another thread allocated the bucket first.
+            Err(_) => drop(unsafe { Box::from_raw(bucket_slice(entries, bucket)) }),
+        }
+    }
+
+    /// Returns an iterator over the values of all the threads in the current process.
+    #[inline]
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        let generation = crate::utils::generation();
+        self.buckets
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(bucket, entries)| {
+                let entries = *entries.get_mut();
+                (!entries.is_null()).then(|| unsafe { &mut *bucket_slice(entries, bucket) })
+            })
+            .flatten()
+            .filter_map(move |slot| {
+                let slot = unsafe { slot.get_mut().as_mut()? };
+                (slot.generation == generation).then_some(&mut slot.value)
+            })
+    }
+
+    /// Drops the values of all the threads in the current process.
+    #[inline]
+    pub fn clear(&mut self) {
+        *self = WipeOnForkThreadLocal::new();
+    }
+}
+
+impl<T: Send> Drop for WipeOnForkThreadLocal<T> {
+    fn drop(&mut self) {
+        let generation = crate::utils::generation();
+        for (bucket, entries) in self.buckets.iter_mut().enumerate() {
+            let entries = *entries.get_mut();
+            if entries.is_null() {
+                continue;
+            }
+            let entries = unsafe { Box::from_raw(bucket_slice(entries, bucket)) };
+            for slot in entries.iter() {
+                let slot = slot.load(Ordering::Relaxed);
+                if !slot.is_null() && unsafe { (*slot).generation } == generation {
+                    drop(unsafe { Box::from_raw(slot) });
+                }
+            }
+        }
+    }
+}
+
+fn bucket_slice<T>(entries: *mut AtomicPtr<Slot<T>>, bucket: usize) -> *mut [AtomicPtr<Slot<T>>] {
+    ptr::slice_from_raw_parts_mut(entries, bucket_size(bucket))
+}
+
+impl<T: Send + Default> WipeOnForkThreadLocal<T> {
+    #[inline]
+    pub fn get_or_default(&self) -> &T {
+        self.get_or(T::default)
+    }
+}
+
+impl<T: Send> Default for WipeOnForkThreadLocal<T> {
+    #[inline]
+    fn default() -> WipeOnForkThreadLocal<T> {
+        WipeOnForkThreadLocal::new()
+    }
+}
+
+impl<T: Send + core::fmt::Debug> core::fmt::Debug for WipeOnForkThreadLocal<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_tuple("WipeOnForkThreadLocal");
+        match self.get() {
+            Some(v) => d.field(v),
+            None => d.field(&format_args!("<uninit>")),
+        };
+        d.finish()
+    }
+}
+
+/// The ID of a thread in the process where it was given, and where the thread's slot is.
+#[derive(Clone, Copy)]
+struct Thread {
+    id: usize,
+    generation: u64,
+    bucket: usize,
+    index: usize,
+}
+
+impl Thread {
+    fn new(id: usize, generation: u64) -> Thread {
+        let bucket = usize::BITS as usize - id.leading_zeros() as usize;
+        let index = if id == 0 { 0 } else { id ^ bucket_size(bucket) };
+        Thread {
+            id,
+            generation,
+            bucket,
+            index,
+        }
+    }
+}
+
+/// Hands out the smallest free IDs, so that the buckets stay few. The IDs of the parent's threads
+/// mean nothing in the child, which starts over with a new `ThreadIds`.
+struct ThreadIds {
+    next: usize,
+    free: BinaryHeap<Reverse<usize>>,
+}
+
+impl ThreadIds {
+    fn new() -> ThreadIds {
+        ThreadIds {
+            next: 0,
+            free: BinaryHeap::new(),
+        }
+    }
+
+    fn alloc(&mut self) -> usize {
+        match self.free.pop() {
+            Some(Reverse(id)) => id,
+            None => {
+                self.next += 1;
+                self.next - 1
+            }
+        }
+    }
+}
+
+static THREAD_IDS: WipeOnForkMutex<ThreadIds> = WipeOnForkMutex::new(ThreadIds::new);
+
+/// Gives the ID of its thread back when the thread exits.
+struct ThreadGuard {
+    thread: Cell<Option<Thread>>,
+}
+
+impl Drop for ThreadGuard {
+    fn drop(&mut self) {
+        // A later destructor of the same thread must not keep using the ID.
+        let _ = THREAD.try_with(|thread| thread.set(None));
+        if let Some(thread) = self.thread.get() {
+            if thread.generation == crate::utils::generation() {
+                let mut ids = THREAD_IDS.lock().unwrap_or_else(PoisonError::into_inner);
+                ids.free.push(Reverse(thread.id));
+            }
+        }
+    }
+}
+
+std::thread_local! {
+    static THREAD: Cell<Option<Thread>> = const { Cell::new(None) };
+    static THREAD_GUARD: ThreadGuard = const {
+        ThreadGuard {
+            thread: Cell::new(None),
+        }
+    };
+}
+
+/// Returns the ID of the current thread in the current process. The forking thread gets a new
+/// one in the child.
+#[inline]
+fn current_thread(generation: u64) -> Thread {
+    match THREAD.with(Cell::get) {
+        Some(thread) if thread.generation == generation => thread,
+        _ => register_thread(generation),
+    }
+}
+
+#[cold]
+fn register_thread(generation: u64) -> Thread {
+    let id = THREAD_IDS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .alloc();
+    let thread = Thread::new(id, generation);
+    THREAD.with(|cell| cell.set(Some(thread)));
+    THREAD_GUARD.with(|guard| guard.thread.set(Some(thread)));
+    thread
+}
@@ -0,0 +1,111 @@
+use crate::{ForkPolicy, WipeOnForkLazyLock};
+use rayon::{Scope, ThreadPool, ThreadPoolBuilder};
+
+fn default_pool() -> ThreadPool {
+    ThreadPoolBuilder::new()
+        .build()
+        .expect("failed to build the rayon thread pool")
+}
+
+/// A rayon `ThreadPool` that is built lazily in each process.
+///
+/// A pool created by the parent is unusable in a child process, since its threads do not exist
+/// there, and `install()` would wait for them forever. This builds a new pool on the first use in
+/// each process instead, and leaks the inherited one rather than dropping it.
+///
+/// Parallel iterators use this pool when they run inside [`install`](Self::install). Outside of
+/// it, they use rayon's global pool, which is not reset in the child.
+///
+/// ```
+/// use rayon::prelude::*;
+/// use wipe_on_fork::WipeOnForkThreadPool;
+///
+/// static POOL: WipeOnForkThreadPool = WipeOnForkThreadPool::with_defaults();
+///
+/// let sum: u64 = POOL.install(|| (1..=100u64).into_par_iter().sum());
+/// assert_eq!(sum, 5050);
+/// ```
+pub struct WipeOnForkThreadPool<F = fn() -> ThreadPool> {
+    pool: WipeOnForkLazyLock<ThreadPool, F>,
+}
+
+impl WipeOnForkThreadPool {
+    /// Creates a holder of a pool built with the default `ThreadPoolBuilder`.
+    #[inline]
+    #[must_use]
+    pub const fn with_defaults() -> WipeOnForkThreadPool {
+        WipeOnForkThreadPool::new(default_pool)
+    }
+}
+
+impl<F: FnMut() -> ThreadPool> WipeOnForkThreadPool<F> {
+    /// Creates a holder of the pool built by `f`.
+    ///
+    /// ```
+    /// use rayon::ThreadPoolBuilder;
+    /// use wipe_on_fork::WipeOnForkThreadPool;
+    ///
+    /// static POOL: WipeOnForkThreadPool = WipeOnForkThreadPool::new(|| {
+    ///     ThreadPoolBuilder::new().num_threads(2).build().unwrap()
+    /// });
+    ///
+    /// assert_eq!(POOL.current_num_threads(), 2);
+    /// ```
+    #[inline]
+    pub const fn new(f: F) -> WipeOnForkThreadPool<F> {
+        WipeOnForkThreadPool {
+            pool: WipeOnForkLazyLock::with_policy(f, ForkPolicy::Forget),
+        }
+    }
+
+    /// Returns the pool of the current process, building it if needed.
+    #[inline]
+    pub fn pool(&self) -> &ThreadPool {
+        &self.pool
+    }
+
+    #[inline]
+    pub fn install<OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        self.pool().install(op)
+    }
+
+    #[inline]
+    pub fn spawn<OP>(&self, op: OP)
+    where
+        OP: FnOnce() + Send + 'static,
+    {
+        self.pool().spawn(op)
+    }
+
+    #[inline]
+    pub fn scope<'scope, OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce(&Scope<'scope>) -> R + Send,
+        R: Send,
+    {
+        self.pool().scope(op)
+    }
+
+    #[inline]
+    pub fn current_num_threads(&self) -> usize {
+        self.pool().current_num_threads()
+    }
+}
+
+impl Default for WipeOnForkThreadPool {
+    #[inline]
+    fn default() -> WipeOnForkThreadPool {
+        WipeOnForkThreadPool::with_defaults()
+    }
+}
+
+impl<F> core::fmt::Debug for WipeOnForkThreadPool<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WipeOnForkThreadPool")
+            .finish_non_exhaustive()
+    }
+}
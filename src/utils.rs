@@ -1,37 +1,365 @@
-use std::sync::Mutex;
+use alloc::boxed::Box;
+use core::sync::atomic::{AtomicPtr, AtomicU64, AtomicU8, Ordering};
+
+// The registration word holds one of these, and while `REGISTERING`, the PID of the process
+// that is registering in the upper 32 bits.
+pub(crate) const UNREGISTERED: u64 = 0;
+pub(crate) const REGISTERING: u64 = 1;
+pub(crate) const REGISTERED: u64 = 2;
+
+const DETECT_AT_FORK: u8 = 0b01;
+const DETECT_PID: u8 = 0b10;
+
+/// How the crate notices that the current process is a forked child.
+///
+/// ```
+/// use wipe_on_fork::{set_fork_detection, ForkDetection};
+///
+/// // Also catch forks that bypass `pthread_atfork`, such as `syscall(SYS_fork)`.
+/// // SAFETY: no wipe-on-fork value has been used yet.
+/// unsafe { set_fork_detection(ForkDetection::Both) };
+/// # unsafe { set_fork_detection(ForkDetection::AtFork) };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ForkDetection {
+    /// Count forks with a `pthread_atfork` child handler. This is the default, and it costs a few
+    /// atomic loads per check. It misses forks that do not run the `pthread_atfork`
+    /// handlers, such as `syscall(SYS_fork)` or `clone()` without `CLONE_VM`.
+    #[default]
+    AtFork,
+    /// Compare the process ID cached by the crate with `getpid()`. This catches every way of
+    /// creating a child process, but costs a system call per check, and cannot tell apart two
+    /// processes that end up with the same PID (see the README for when this can happen).
+    Pid,
+    /// Use both of the above. A fork is counted once even if both notice it.
+    Both,
+}
+
+impl ForkDetection {
+    const fn to_bits(self) -> u8 {
+        match self {
+            ForkDetection::AtFork => DETECT_AT_FORK,
+            ForkDetection::Pid => DETECT_PID,
+            ForkDetection::Both => DETECT_AT_FORK | DETECT_PID,
+        }
+    }
+
+    const fn from_bits(bits: u8) -> Self {
+        match bits {
+            DETECT_PID => ForkDetection::Pid,
+            DETECT_AT_FORK => ForkDetection::AtFork,
+            _ => ForkDetection::Both,
+        }
+    }
+}
+
+/// Chooses how forks are detected, for all the `WipeOnFork*` types in the process.
+///
+/// The choice is inherited by child processes.
+///
+/// # Safety
+///
+/// In a process that was created without running the `pthread_atfork` handlers, e.g., by
+/// `syscall(SYS_fork)`, turning on PID detection counts that fork at once, so values may be wiped
+/// while references to them are alive. As with [`set_fork_detector`], no wipe-on-fork value
+/// initialized before the call may be accessed after it, and no other thread may access one during
+/// the call. Calling this early in `main`, before any wipe-on-fork value is used, satisfies both.
+pub unsafe fn set_fork_detection(detection: ForkDetection) {
+    GENERATION.get();
+    GENERATION
+        .detection
+        .store(detection.to_bits(), Ordering::Release);
+}
+
+/// Returns how forks are currently detected.
+///
+/// ```
+/// use wipe_on_fork::{fork_detection, ForkDetection};
+///
+/// assert_eq!(fork_detection(), ForkDetection::AtFork);
+/// ```
+pub fn fork_detection() -> ForkDetection {
+    ForkDetection::from_bits(GENERATION.detection.load(Ordering::Acquire))
+}
+
+/// A generation together with the PID of the process that it belongs to, packed in one word so
+/// that they always change together: the generation in the upper 32 bits, and the PID in the lower
+/// 32 bits, where 0 means that no PID was recorded yet.
+pub(crate) struct PidGeneration(AtomicU64);
+
+impl PidGeneration {
+    const fn new() -> Self {
+        PidGeneration(AtomicU64::new(0))
+    }
+
+    #[inline]
+    fn generation(&self) -> u64 {
+        self.0.load(Ordering::Acquire) >> 32
+    }
+
+    /// Bumps the generation if `pid` is not the recorded PID, or only records `pid` if no PID was
+    /// recorded yet. Both are swapped at once, so exactly one thread bumps the generation for each
+    /// new PID, and a thread that sees the new PID also sees the new generation.
+    #[inline]
+    fn update(&self, pid: u32) {
+        let word = self.0.load(Ordering::Acquire);
+        if word as u32 != pid {
+            self.update_slow(word, pid);
+        }
+    }
+
+    #[cold]
+    fn update_slow(&self, mut word: u64, pid: u32) {
+        loop {
+            let new = if word as u32 == 0 {
+                word | pid as u64
+            } else {
+                (((word >> 32) + 1) << 32) | pid as u64
+            };
+            match self
+                .0
+                .compare_exchange_weak(word, new, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return,
+                Err(actual) if actual as u32 == pid => return,
+                Err(actual) => word = actual,
+            }
+        }
+    }
+
+    /// Records `pid` if no PID was recorded yet.
+    #[inline]
+    fn record(&self, pid: u32) {
+        let word = self.0.load(Ordering::Acquire);
+        if word as u32 == 0 {
+            self.update_slow(word, pid);
+        }
+    }
+
+    /// Bumps the generation and records `pid`, without looking at the recorded PID. Only for the
+    /// `pthread_atfork` child handler, where no other thread exists.
+    #[cfg(unix)]
+    #[inline]
+    fn bump(&self, pid: u32) {
+        let word = self.0.load(Ordering::Acquire);
+        self.0
+            .store((((word >> 32) + 1) << 32) | pid as u64, Ordering::Release);
+    }
+}
 
 pub struct GenerationCounter {
-    pub(crate) gen: Mutex<Option<u64>>,
+    pub(crate) gen: PidGeneration,
+    pub(crate) registration: AtomicU64,
+    pub(crate) detection: AtomicU8,
 }
 
 impl GenerationCounter {
     pub const fn new() -> Self {
         Self {
-            gen: Mutex::new(None),
+            gen: PidGeneration::new(),
+            registration: AtomicU64::new(UNREGISTERED),
+            detection: AtomicU8::new(DETECT_AT_FORK),
         }
     }
 
+    #[inline]
     pub fn get(&self) -> u64 {
-        let mut lock = self.gen.lock().unwrap();
-        if lock.is_some() {
-            return lock.unwrap();
-        } else {
-            unsafe {
-                libc::pthread_atfork(None, None, Some(update_generations));
+        if self.registration.load(Ordering::Acquire) != REGISTERED {
+            self.register();
+        }
+        if self.detection.load(Ordering::Relaxed) & DETECT_PID != 0 {
+            self.gen.update(current_pid());
+        }
+        self.gen.generation()
+    }
+
+    #[cold]
+    fn register(&self) {
+        let pid = current_pid();
+        let registering = REGISTERING | (pid as u64) << 32;
+        let mut word = self.registration.load(Ordering::Acquire);
+        loop {
+            if word == REGISTERED {
+                return;
+            }
+            if word == registering {
+                // Another thread is registering the fork handler. Wait for it, so that a value
+                // initialized by this thread cannot miss a fork that happens right afterwards.
+                crate::futex::yield_now();
+                word = self.registration.load(Ordering::Acquire);
+                continue;
+            }
+            // Either nobody has started, or the process forked while a thread of the parent was
+            // registering, before `pthread_atfork` returned (afterwards, the child handler would
+            // have marked the registration as done). That thread does not exist here to finish,
+            // and the child has no handler yet, so this thread takes over.
+            match self.registration.compare_exchange(
+                word,
+                registering,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(actual) => word = actual,
             }
-            *lock = Some(0u64);
-            return 0u64;
         }
+
+        self.gen.record(pid);
+        #[cfg(unix)]
+        unsafe {
+            libc::pthread_atfork(
+                Some(prepare_fork),
+                Some(after_fork_in_parent),
+                Some(update_generations),
+            );
+        }
+        self.registration.store(REGISTERED, Ordering::Release);
     }
 }
 
 pub(crate) static GENERATION: GenerationCounter = GenerationCounter::new();
 
-unsafe extern "C" fn update_generations() {
-    let mut lock = GENERATION.gen.lock().unwrap();
-    if lock.is_some() {
-        *lock = Some(lock.unwrap() + 1);
+#[cfg(unix)]
+#[inline]
+fn current_pid() -> u32 {
+    unsafe { libc::getpid() as u32 }
+}
+
+#[cfg(not(unix))]
+#[inline]
+fn current_pid() -> u32 {
+    std::process::id()
+}
+
+/// A source of fork generations, which replaces the built-in detection chosen with
+/// [`set_fork_detection`] once installed with [`set_fork_detector`].
+///
+/// [`generation`](Self::generation) is called on every access to a wipe-on-fork value, so it
+/// should be cheap.
+///
+/// This is meant for environments where the built-in detection does not work, such as a custom
+/// runtime that creates processes in its own way. The implementations below can be combined with
+/// such a mechanism.
+///
+/// # Safety
+///
+/// The cells wipe their value when the generation changes, even if a reference to the value is
+/// still alive. So [`generation`](Self::generation) must return the same number for as long as
+/// the process is not forked, and may only change in a forked child that is still
+/// single-threaded and holds no such reference, e.g., from a `pthread_atfork` child handler. It
+/// must change in each forked child.
+///
+/// ```
+/// use std::sync::atomic::{AtomicU64, Ordering};
+///
+/// use wipe_on_fork::{set_fork_detector, AtForkDetector, ForkDetector};
+///
+/// // Bumped by the runtime in each new process, before it starts any thread.
+/// static SPAWNS: AtomicU64 = AtomicU64::new(0);
+///
+/// struct RuntimeDetector;
+///
+/// // SAFETY: both numbers only change in a new process, before it starts any thread.
+/// unsafe impl ForkDetector for RuntimeDetector {
+///     fn generation(&self) -> u64 {
+///         AtForkDetector.generation() + SPAWNS.load(Ordering::Acquire)
+///     }
+/// }
+///
+/// // SAFETY: no wipe-on-fork value has been used yet.
+/// unsafe { set_fork_detector(&RuntimeDetector) };
+/// assert_eq!(wipe_on_fork::fork::generation(), 0);
+/// ```
+pub unsafe trait ForkDetector: Sync {
+    fn generation(&self) -> u64;
+}
+
+/// Counts the forks that run the `pthread_atfork` child handlers, like
+/// [`ForkDetection::AtFork`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AtForkDetector;
+
+// The generation is only bumped by the child handler, which runs before `fork()` returns.
+unsafe impl ForkDetector for AtForkDetector {
+    #[inline]
+    fn generation(&self) -> u64 {
+        if GENERATION.registration.load(Ordering::Acquire) != REGISTERED {
+            GENERATION.register();
+        }
+        AT_FORK_GENERATION.load(Ordering::Acquire)
+    }
+}
+
+/// Counts the changes of `getpid()`, like [`ForkDetection::Pid`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PidDetector;
+
+// The generation is bumped at most once per PID, so only in a new process.
+unsafe impl ForkDetector for PidDetector {
+    #[inline]
+    fn generation(&self) -> u64 {
+        // The first call only records the PID.
+        PID_DETECTOR.update(current_pid());
+        PID_DETECTOR.generation()
+    }
+}
+
+// Bumped by the `pthread_atfork` child handler in every mode, for `AtForkDetector`.
+static AT_FORK_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+static PID_DETECTOR: PidGeneration = PidGeneration::new();
+
+static DETECTOR: AtomicPtr<&'static dyn ForkDetector> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Replaces how forks are detected with `detector`, for all the `WipeOnFork*` types and
+/// [`fork::generation`](crate::fork::generation) in the process.
+///
+/// The callbacks of [`fork`](crate::fork) still run from the `pthread_atfork` handlers.
+///
+/// # Safety
+///
+/// Values initialized before the switch compare their generation with a number from another
+/// source, so they may be wiped while references to them are alive. No wipe-on-fork value
+/// initialized before the call may be accessed after it, and no other thread may access one
+/// during the call. Calling this early in `main`, before any wipe-on-fork value is used, satisfies
+/// both.
+pub unsafe fn set_fork_detector(detector: &'static dyn ForkDetector) {
+    // The previous detector is leaked, since other threads may still be calling it.
+    let detector = Box::into_raw(Box::new(detector));
+    DETECTOR.store(detector, Ordering::Release);
+}
+
+/// Returns the generation of the current process, as seen by the installed [`ForkDetector`] or
+/// else the built-in detection.
+#[inline]
+pub(crate) fn generation() -> u64 {
+    let detector = DETECTOR.load(Ordering::Acquire);
+    if detector.is_null() {
+        GENERATION.get()
     } else {
-        panic!("The generation counter is expected to have started.");
+        unsafe { (*detector).generation() }
+    }
+}
+
+#[cfg(unix)]
+unsafe extern "C" fn prepare_fork() {
+    crate::fork::run_prepare_callbacks();
+}
+
+#[cfg(unix)]
+unsafe extern "C" fn after_fork_in_parent() {
+    crate::fork::run_parent_callbacks();
+}
+
+// This handler runs in the child right after `fork()`, where only async-signal-safe operations
+// are allowed. It must not lock, allocate, or panic, so it only touches atomics and `getpid()`
+// before handing over to the callbacks registered through `crate::fork::on_child`.
+#[cfg(unix)]
+unsafe extern "C" fn update_generations() {
+    AT_FORK_GENERATION.fetch_add(1, Ordering::AcqRel);
+    if GENERATION.detection.load(Ordering::Acquire) & DETECT_AT_FORK != 0 {
+        GENERATION.gen.bump(libc::getpid() as u32);
     }
+    GENERATION.registration.store(REGISTERED, Ordering::Release);
+    crate::fork::run_child_callbacks();
 }